@@ -0,0 +1,299 @@
+//! This module calculates the areas and perimeters of polygons, both on the GRS80 ellipsoid
+//! and on the National Grid
+//!
+//! The ellipsoidal (geodesic) area is that enclosed by geodesics joining the ETRS89 vertices.
+//! The grid area is the planar area enclosed by the OSGB36 vertices, and differs from the
+//! ellipsoidal area by the square of the scale factor: about −800 ppm on the central
+//! meridian, rising to about +1400 ppm at 700 km E, and about +3100 ppm at 0 E. Reducing
+//! areas measured on the ground to the ellipsoid introduces the square of the elevation
+//! factor as well.
+use conversions::{convert_osgb36, convert_osgb36_to_etrs89_3d, convert_osgb36_to_ll};
+use ellipsoid::Ellipsoid;
+use errors::ConversionError;
+use geodesic::geodesic_polygon_area;
+use scale::scale_and_convergence_osgb36;
+
+type Ring = Vec<(f64, f64)>;
+
+/// A polygon, made up of an exterior ring and zero or more interior rings (holes)
+///
+/// Each ring is a list of vertices, which may be given in either direction, and may repeat
+/// the first vertex at the end
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub interiors: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    pub fn new(exterior: Vec<(f64, f64)>, interiors: Vec<Vec<(f64, f64)>>) -> Polygon {
+        Polygon {
+            exterior: exterior,
+            interiors: interiors,
+        }
+    }
+
+    // The exterior ring, followed by the holes
+    fn rings(&self) -> Vec<&Ring> {
+        let mut rings = vec![&self.exterior];
+        rings.extend(self.interiors.iter());
+        rings
+    }
+}
+
+/// The areas and perimeters of a polygon on the ellipsoid and on the National Grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolygonArea {
+    /// The area on the GRS80 ellipsoid, less that of any holes (m²)
+    pub geodesic_area: f64,
+    /// The total length of the polygon's rings on the GRS80 ellipsoid, including holes (m)
+    pub geodesic_perimeter: f64,
+    /// The planar area on the National Grid, less that of any holes (m²)
+    pub grid_area: f64,
+    /// The total length of the polygon's rings on the National Grid, including holes (m)
+    pub grid_perimeter: f64,
+    /// The mean scale factor over the polygon: the square root of the grid area divided by
+    /// the geodesic area
+    pub scale_factor: f64,
+    /// The ellipsoidal distance divided by the distance at the polygon's ellipsoidal height.
+    /// This is 1 if no height was given
+    pub elevation_factor: f64,
+    /// The product of the scale factor and the elevation factor
+    pub combined_scale_factor: f64,
+}
+
+impl PolygonArea {
+    /// Convert an area measured on the ground to a grid area
+    pub fn ground_to_grid(&self, area: &f64) -> f64 {
+        area * self.combined_scale_factor.powi(2)
+    }
+
+    /// Convert a grid area to an area on the ground
+    pub fn grid_to_ground(&self, area: &f64) -> f64 {
+        area / self.combined_scale_factor.powi(2)
+    }
+}
+
+// The planar area and perimeter of a ring
+fn planar_area(ring: &[(f64, f64)]) -> (f64, f64) {
+    let n = ring.len();
+    let (mut twice_area, mut perimeter) = (0., 0.);
+    for i in 0..n {
+        let (x1, y1) = ring[i];
+        let (x2, y2) = ring[(i + 1) % n];
+        twice_area += x1 * y2 - x2 * y1;
+        perimeter += (x2 - x1).hypot(y2 - y1);
+    }
+    (twice_area.abs() / 2., perimeter)
+}
+
+// Apply a conversion to every vertex of every ring
+fn convert_rings<F>(polygons: &[Polygon], f: F) -> Result<Vec<Vec<Ring>>, ConversionError>
+    where F: Fn(&f64, &f64) -> Result<(f64, f64), ConversionError>
+{
+    polygons.iter()
+        .map(|polygon| {
+            polygon.rings()
+                .iter()
+                .map(|ring| ring.iter().map(|&(x, y)| f(&x, &y)).collect())
+                .collect()
+        })
+        .collect()
+}
+
+// Measure polygons given both as ETRS89 longitudes and latitudes, and as OSGB36 Eastings and
+// Northings. The elevation factor is calculated at the first vertex
+fn measure(lonlat: &[Vec<Ring>],
+           grid: &[Vec<Ring>],
+           height: Option<f64>)
+           -> Result<PolygonArea, ConversionError> {
+    let (mut geodesic_area, mut geodesic_perimeter) = (0., 0.);
+    let (mut grid_area, mut grid_perimeter) = (0., 0.);
+    for (lonlat_rings, grid_rings) in lonlat.iter().zip(grid) {
+        for (i, (lonlat_ring, grid_ring)) in lonlat_rings.iter().zip(grid_rings).enumerate() {
+            let (lons, lats): (Vec<f64>, Vec<f64>) = lonlat_ring.iter().cloned().unzip();
+            let (area, perimeter) = geodesic_polygon_area(&lons, &lats, &Ellipsoid::GRS80)?;
+            let (planar, planar_perimeter) = planar_area(grid_ring);
+            // Subtract the areas of holes
+            let sign = if i == 0 { 1. } else { -1. };
+            geodesic_area += sign * area;
+            grid_area += sign * planar;
+            geodesic_perimeter += perimeter;
+            grid_perimeter += planar_perimeter;
+        }
+    }
+    // The first vertex of the first non-empty ring, both as a longitude and latitude, and as
+    // an Easting and Northing
+    let first = lonlat.iter()
+        .zip(grid)
+        .flat_map(|(lonlat_rings, grid_rings)| lonlat_rings.iter().zip(grid_rings))
+        .flat_map(|(lonlat_ring, grid_ring)| lonlat_ring.iter().zip(grid_ring).next())
+        .next();
+    let scale_factor = match first {
+        _ if geodesic_area > 0. => (grid_area / geodesic_area).sqrt(),
+        Some((_, &(e, n))) => scale_and_convergence_osgb36(&e, &n)?.0,
+        None => 1.,
+    };
+    let elevation_factor = match (height, first) {
+        (Some(height), Some((&(_, lat), &(e, n)))) => {
+            let (_, _, h) = convert_osgb36_to_etrs89_3d(&e, &n, &height)?;
+            let radius = Ellipsoid::GRS80.mean_radius(&lat);
+            radius / (radius + h)
+        }
+        _ => 1.,
+    };
+    Ok(PolygonArea {
+        geodesic_area: geodesic_area,
+        geodesic_perimeter: geodesic_perimeter,
+        grid_area: grid_area,
+        grid_perimeter: grid_perimeter,
+        scale_factor: scale_factor,
+        elevation_factor: elevation_factor,
+        combined_scale_factor: scale_factor * elevation_factor,
+    })
+}
+
+/// Calculate the geodesic and grid areas and perimeters of a polygon whose vertices are
+/// ETRS89 longitudes and latitudes, using OSTN15 data
+///
+/// If `height` gives the mean ODN height of the area, its ellipsoidal height is found using
+/// OSGM15, and used to calculate the elevation factor
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{polygon_area, Polygon};
+/// let field = Polygon::new(vec![(-1.5, 52.), (-1.49, 52.), (-1.49, 52.01), (-1.5, 52.01)],
+///                          vec![]);
+/// let area = polygon_area(&field, Some(120.)).unwrap();
+/// let grid_area = area.ground_to_grid(&area.geodesic_area);
+/// ```
+pub fn polygon_area(polygon: &Polygon,
+                    height: Option<f64>)
+                    -> Result<PolygonArea, ConversionError> {
+    multipolygon_area(&[polygon.clone()], height)
+}
+
+/// Calculate the geodesic and grid areas and perimeters of a polygon whose vertices are
+/// OSGB36 Eastings and Northings, using OSTN15 data
+///
+/// If `height` gives the mean ODN height of the area, its ellipsoidal height is found using
+/// OSGM15, and used to calculate the elevation factor
+pub fn polygon_area_osgb36(polygon: &Polygon,
+                           height: Option<f64>)
+                           -> Result<PolygonArea, ConversionError> {
+    multipolygon_area_osgb36(&[polygon.clone()], height)
+}
+
+/// As [`polygon_area`](fn.polygon_area.html), but for several polygons, whose areas and
+/// perimeters are summed
+pub fn multipolygon_area(polygons: &[Polygon],
+                         height: Option<f64>)
+                         -> Result<PolygonArea, ConversionError> {
+    let lonlat = convert_rings(polygons, |lon, lat| Ok((*lon, *lat)))?;
+    let grid = convert_rings(polygons, convert_osgb36)?;
+    measure(&lonlat, &grid, height)
+}
+
+/// As [`polygon_area_osgb36`](fn.polygon_area_osgb36.html), but for several polygons, whose
+/// areas and perimeters are summed
+pub fn multipolygon_area_osgb36(polygons: &[Polygon],
+                                height: Option<f64>)
+                                -> Result<PolygonArea, ConversionError> {
+    let lonlat = convert_rings(polygons, convert_osgb36_to_ll)?;
+    let grid = convert_rings(polygons, |e, n| Ok((*e, *n)))?;
+    measure(&lonlat, &grid, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use conversions::F0;
+
+    fn square(e: f64, n: f64, side: f64) -> Ring {
+        vec![(e, n), (e + side, n), (e + side, n + side), (e, n + side)]
+    }
+
+    #[test]
+    fn test_planar_area() {
+        let (area, perimeter) = planar_area(&square(0., 0., 10.));
+        assert_eq!((100., 40.), (area, perimeter));
+        // Clockwise, and closed
+        let (area, _) = planar_area(&[(0., 0.), (0., 10.), (10., 10.), (10., 0.), (0., 0.)]);
+        assert_eq!(100., area);
+    }
+
+    #[test]
+    fn test_central_meridian_area() {
+        let area = polygon_area_osgb36(&Polygon::new(square(399500., 499500., 1000.), vec![]),
+                                       None)
+            .unwrap();
+        assert_eq!(1000000., area.grid_area);
+        assert_eq!(4000., area.grid_perimeter);
+        assert!((area.scale_factor - F0).abs() < 0.00001);
+        assert!((area.grid_area / area.geodesic_area - F0.powi(2)).abs() < 0.00002);
+        assert!((area.geodesic_perimeter * area.scale_factor - 4000.).abs() < 0.01);
+        assert_eq!(1., area.elevation_factor);
+    }
+
+    #[test]
+    fn test_area_with_hole() {
+        let outer = square(650000., 310000., 2000.);
+        let hole = square(650500., 310500., 1000.);
+        let solid = polygon_area_osgb36(&Polygon::new(outer.clone(), vec![]), None).unwrap();
+        let holed = polygon_area_osgb36(&Polygon::new(outer, vec![hole.clone()]), None).unwrap();
+        let inner = polygon_area_osgb36(&Polygon::new(hole, vec![]), None).unwrap();
+        assert_eq!(3000000., holed.grid_area);
+        assert_eq!(12000., holed.grid_perimeter);
+        assert!((solid.geodesic_area - inner.geodesic_area - holed.geodesic_area).abs() < 0.01);
+        // East of the grid's true-scale lines, the grid area is larger
+        assert!(holed.scale_factor > 1.0003);
+    }
+
+    #[test]
+    fn test_lonlat_polygon() {
+        let field = Polygon::new(vec![(-1.5, 52.), (-1.49, 52.), (-1.49, 52.01), (-1.5, 52.01)],
+                                 vec![]);
+        let area = polygon_area(&field, None).unwrap();
+        let grid = Polygon::new(field.exterior
+                                    .iter()
+                                    .map(|&(lon, lat)| convert_osgb36(&lon, &lat).unwrap())
+                                    .collect(),
+                                vec![]);
+        let grid_area = polygon_area_osgb36(&grid, None).unwrap();
+        assert!((area.grid_area - grid_area.grid_area).abs() < 0.001);
+        assert!((area.geodesic_area - grid_area.geodesic_area).abs() < 1.);
+        assert!(area.geodesic_area > 760000. && area.geodesic_area < 770000.);
+    }
+
+    #[test]
+    fn test_multipolygon_area() {
+        let first = Polygon::new(square(400000., 500000., 100.), vec![]);
+        let second = Polygon::new(square(410000., 500000., 200.), vec![]);
+        let both = multipolygon_area_osgb36(&[first.clone(), second.clone()], None).unwrap();
+        let first = polygon_area_osgb36(&first, None).unwrap();
+        let second = polygon_area_osgb36(&second, None).unwrap();
+        assert_eq!(50000., both.grid_area);
+        assert!((both.geodesic_area - first.geodesic_area - second.geodesic_area).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_area_elevation_factor() {
+        let field = Polygon::new(square(400000., 500000., 100.), vec![]);
+        let area = polygon_area_osgb36(&field, Some(1000.)).unwrap();
+        assert!(area.elevation_factor < 0.99985);
+        assert!(area.combined_scale_factor < area.scale_factor);
+        let ground = area.grid_to_ground(&area.grid_area);
+        assert!(ground > area.geodesic_area);
+        assert!((area.ground_to_grid(&ground) - area.grid_area).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_elevation_factor_skips_empty_polygons() {
+        let field = Polygon::new(square(400000., 500000., 100.), vec![]);
+        let empty = Polygon::new(vec![], vec![]);
+        let area = polygon_area_osgb36(&field, Some(1000.)).unwrap();
+        let both = multipolygon_area_osgb36(&[empty, field], Some(1000.)).unwrap();
+        assert_eq!(area.elevation_factor, both.elevation_factor);
+    }
+}
@@ -0,0 +1,226 @@
+//! This module provides conversions between true azimuths and National Grid bearings
+//!
+//! A grid bearing is a true azimuth minus the meridian convergence at the point. For lines
+//! longer than a few kilometres, the arc-to-chord correction `t − T` is also applied, so
+//! that the grid bearing is that of the straight line between the two points on the grid,
+//! rather than of the curved image of the geodesic.
+use std::fmt;
+
+use conversions::{MAX_EASTING, MAX_NORTHING};
+use errors::{Axis, ConversionError};
+use scale::{scale_and_convergence, scale_and_convergence_osgb36};
+use transverse_mercator::TransverseMercator;
+use utils::{check, normalise_bearing};
+
+// Dms seconds are kept to the nearest thousandth, the precision at which they're displayed
+const MILLISECONDS_PER_DEGREE: u64 = 3600000;
+const MILLISECONDS_PER_MINUTE: u64 = 60000;
+
+/// An angle in degrees, minutes and seconds, as used for bearings between 0° and 360°
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dms {
+    pub degrees: u32,
+    pub minutes: u32,
+    pub seconds: f64,
+}
+
+impl Dms {
+    pub fn new(degrees: u32, minutes: u32, seconds: f64) -> Dms {
+        Dms {
+            degrees: degrees,
+            minutes: minutes,
+            seconds: seconds,
+        }
+    }
+
+    /// Convert a bearing in decimal degrees, which is first wrapped into the range [0, 360)
+    ///
+    /// The seconds are rounded to the nearest thousandth, the precision at which they're
+    /// displayed, and any overflow is carried into the minutes and degrees
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::Dms;
+    /// let dms = Dms::from_degrees(&-0.5);
+    /// assert_eq!((359, 30), (dms.degrees, dms.minutes));
+    /// assert_eq!("359° 30′ 0.000″", dms.to_string());
+    /// assert_eq!("1° 0′ 0.000″", Dms::from_degrees(&0.99999999).to_string());
+    /// ```
+    pub fn from_degrees(bearing: &f64) -> Dms {
+        let total = (normalise_bearing(*bearing) * MILLISECONDS_PER_DEGREE as f64).round() as u64;
+        // A bearing just below 360° rounds up to north
+        let total = total % (360 * MILLISECONDS_PER_DEGREE);
+        Dms::new((total / MILLISECONDS_PER_DEGREE) as u32,
+                 (total / MILLISECONDS_PER_MINUTE % 60) as u32,
+                 (total % MILLISECONDS_PER_MINUTE) as f64 / 1000.)
+    }
+
+    /// The angle in decimal degrees
+    pub fn to_degrees(&self) -> f64 {
+        self.degrees as f64 + self.minutes as f64 / 60. + self.seconds / 3600.
+    }
+}
+
+impl fmt::Display for Dms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}° {}′ {:.3}″", self.degrees, self.minutes, self.seconds)
+    }
+}
+
+// The arc-to-chord correction on the National Grid, if a far end is given
+#[allow(non_snake_case)]
+fn arc_to_chord(projection: &TransverseMercator,
+                E: &f64,
+                N: &f64,
+                to: Option<(f64, f64)>)
+                -> Result<f64, ConversionError> {
+    match to {
+        Some((e, n)) => projection.arc_to_chord(E, N, &e, &n),
+        None => Ok(0.),
+    }
+}
+
+/// Convert a true azimuth (decimal degrees) at an ETRS89 longitude and latitude to a
+/// National Grid bearing
+///
+/// If the longitude and latitude of the far end of the line are given as `to`, the
+/// arc-to-chord correction is applied
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::true_to_grid_bearing;
+/// // Caister Water Tower is east of the central meridian, so grid north is east of true north
+/// let bearing = true_to_grid_bearing(&1.716073973, &52.658007833, &45., None).unwrap();
+/// assert!((bearing - 42.044078).abs() < 0.000001);
+/// ```
+pub fn true_to_grid_bearing(longitude: &f64,
+                            latitude: &f64,
+                            azimuth: &f64,
+                            to: Option<(f64, f64)>)
+                            -> Result<f64, ConversionError> {
+    let azimuth = check(*azimuth, (-360., 360.), Axis::Bearing)?;
+    let (_, convergence) = scale_and_convergence(longitude, latitude)?;
+    let projection = TransverseMercator::NATIONAL_GRID_ETRS89;
+    let end = match to {
+        Some((lon, lat)) => Some(projection.forward(&lon, &lat)?),
+        None => None,
+    };
+    let (e, n) = projection.forward(longitude, latitude)?;
+    let correction = arc_to_chord(&projection, &e, &n, end)?;
+    Ok(normalise_bearing(azimuth - convergence + correction))
+}
+
+/// Convert a National Grid bearing (decimal degrees) at an ETRS89 longitude and latitude
+/// to a true azimuth
+///
+/// If the longitude and latitude of the far end of the line are given as `to`, the
+/// arc-to-chord correction is removed
+pub fn grid_to_true_bearing(longitude: &f64,
+                            latitude: &f64,
+                            bearing: &f64,
+                            to: Option<(f64, f64)>)
+                            -> Result<f64, ConversionError> {
+    let bearing = check(*bearing, (-360., 360.), Axis::Bearing)?;
+    // Applying the forward conversion to a zero azimuth gives the total correction
+    let offset = true_to_grid_bearing(longitude, latitude, &0., to)?;
+    Ok(normalise_bearing(bearing - offset))
+}
+
+/// Convert a true azimuth (decimal degrees) at an OSGB36 Easting and Northing to a
+/// National Grid bearing
+///
+/// If the Easting and Northing of the far end of the line are given as `to`, the
+/// arc-to-chord correction is applied
+#[allow(non_snake_case)]
+pub fn true_to_grid_bearing_osgb36(E: &f64,
+                                   N: &f64,
+                                   azimuth: &f64,
+                                   to: Option<(f64, f64)>)
+                                   -> Result<f64, ConversionError> {
+    let azimuth = check(*azimuth, (-360., 360.), Axis::Bearing)?;
+    let (_, convergence) = scale_and_convergence_osgb36(E, N)?;
+    if let Some((e, n)) = to {
+        check(e, (0.000, MAX_EASTING), Axis::Easting)?;
+        check(n, (0.000, MAX_NORTHING), Axis::Northing)?;
+    }
+    let correction = arc_to_chord(&TransverseMercator::NATIONAL_GRID, E, N, to)?;
+    Ok(normalise_bearing(azimuth - convergence + correction))
+}
+
+/// Convert a National Grid bearing (decimal degrees) at an OSGB36 Easting and Northing
+/// to a true azimuth
+///
+/// If the Easting and Northing of the far end of the line are given as `to`, the
+/// arc-to-chord correction is removed
+#[allow(non_snake_case)]
+pub fn grid_to_true_bearing_osgb36(E: &f64,
+                                   N: &f64,
+                                   bearing: &f64,
+                                   to: Option<(f64, f64)>)
+                                   -> Result<f64, ConversionError> {
+    let bearing = check(*bearing, (-360., 360.), Axis::Bearing)?;
+    let offset = true_to_grid_bearing_osgb36(E, N, &0., to)?;
+    Ok(normalise_bearing(bearing - offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dms() {
+        let dms = Dms::from_degrees(&123.5125);
+        assert_eq!((123, 30), (dms.degrees, dms.minutes));
+        assert!((dms.seconds - 45.).abs() < 0.000001);
+        assert!((Dms::new(123, 30, 45.).to_degrees() - 123.5125).abs() < 1e-12);
+        assert_eq!("123° 30′ 45.000″", Dms::new(123, 30, 45.).to_string());
+    }
+
+    #[test]
+    fn test_dms_carry() {
+        // 59.99997″ rounds up to a whole minute, which carries into the degrees
+        assert_eq!(Dms::new(1, 0, 0.), Dms::from_degrees(&0.99999999));
+        assert_eq!(Dms::new(10, 31, 0.), Dms::from_degrees(&(10. + 30.99999999 / 60.)));
+        assert_eq!(Dms::new(0, 0, 0.), Dms::from_degrees(&359.99999999));
+        assert_eq!(Dms::new(0, 0, 0.001), Dms::from_degrees(&(0.0012 / 3600.)));
+    }
+
+    #[test]
+    fn test_bearing_round_trip() {
+        let grid = true_to_grid_bearing(&1.716073973, &52.658007833, &45., None).unwrap();
+        // East of the central meridian, grid north is east of true north
+        assert!(grid > 42. && grid < 42.1);
+        let azimuth = grid_to_true_bearing(&1.716073973, &52.658007833, &grid, None).unwrap();
+        assert!((azimuth - 45.).abs() < 1e-9);
+        // Wraps past north
+        let grid = true_to_grid_bearing(&1.716073973, &52.658007833, &1., None).unwrap();
+        assert!(grid > 358.);
+    }
+
+    #[test]
+    fn test_meridian_chord() {
+        // A meridian is a geodesic, so the grid bearing of the chord between two points
+        // on one should match the bearing calculated with the arc-to-chord correction
+        let tm = TransverseMercator::NATIONAL_GRID;
+        let (e1, n1) = tm.forward(&1., &52.).unwrap();
+        let (e2, n2) = tm.forward(&1., &53.).unwrap();
+        let chord = normalise_bearing((e2 - e1).atan2(n2 - n1).to_degrees());
+        let grid = true_to_grid_bearing_osgb36(&e1, &n1, &0., Some((e2, n2))).unwrap();
+        assert!((grid - chord).abs() < 0.00001);
+        // Without the correction, the bearing is out by about a minute of arc
+        let uncorrected = true_to_grid_bearing_osgb36(&e1, &n1, &0., None).unwrap();
+        assert!((uncorrected - chord).abs() > 0.01);
+        let azimuth = grid_to_true_bearing_osgb36(&e1, &n1, &chord, Some((e2, n2))).unwrap();
+        assert!(azimuth < 0.00001 || azimuth > 359.99999);
+    }
+
+    #[test]
+    fn test_bad_bearing() {
+        match true_to_grid_bearing_osgb36(&651409.792, &313177.448, &f64::NAN, None) {
+            Err(ConversionError::NonFinite { axis: Axis::Bearing, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -3,11 +3,8 @@
 
 // Constants used for coordinate conversions
 //
-// Ellipsoids
-pub const AIRY_1830_SEMI_MAJOR: f64 = 6377563.396;
-pub const AIRY_1830_SEMI_MINOR: f64 = 6356256.909;
-pub const GRS80_SEMI_MAJOR: f64 = 6378137.000;
-pub const GRS80_SEMI_MINOR: f64 = 6356752.3141;
+// Ellipsoids are provided by the ellipsoid module
+//
 // Northing & easting of true origin (m)
 pub const TRUE_ORIGIN_NORTHING: f64 = -100000.;
 pub const TRUE_ORIGIN_EASTING: f64 = 400000.;
@@ -24,7 +21,6 @@ pub const RZS: f64 = -0.8421;
 pub const S: f64 = 20.4894 * 0.000001;
 // etc
 pub const PI: f64 = f64::consts::PI;
-pub const RAD: f64 = PI / 180.;
 pub const MAX_EASTING: f64 = 700000.000;
 pub const MAX_NORTHING: f64 = 1250000.000;
 
@@ -32,33 +28,30 @@ pub const MIN_LONGITUDE: f64 = -7.5600;
 pub const MAX_LONGITUDE: f64 = 1.7800;
 pub const MIN_LATITUDE: f64 = 49.9600;
 pub const MAX_LATITUDE: f64 = 60.8400;
+// Web Mercator covers a square, whose edges lie at these latitudes and half-widths (m)
+pub const MAX_WEB_MERCATOR_LATITUDE: f64 = 85.0511287798;
+pub const WEB_MERCATOR_EXTENT: f64 = 20037508.34;
 
-// lon and lat of true origin
-const LAM0: f64 = RAD * -2.0;
-const PHI0: f64 = RAD * 49.0;
-
-// Easting and Northing of origin
-const E0: f64 = TRUE_ORIGIN_EASTING;
-const N0: f64 = TRUE_ORIGIN_NORTHING;
 // convergence factor
-const F0: f64 = 0.9996012717;
+pub const F0: f64 = 0.9996012717;
+// Give up on the iterative OSTN15 inverse transformation after this many steps
+const MAX_ITERATIONS: usize = 100;
 
 extern crate libc;
 use self::libc::c_double;
-use std::mem;
 use std::f64;
 
+use errors::{Axis, ConversionError};
 use utils::check;
 use utils::round_to_eight;
 use utils::ostn15_shifts;
+use utils::ostn15_shifts_with_datum;
+use datum::{DatumFlags, VerticalDatum};
+use ellipsoid::Ellipsoid;
+use helmert::Helmert;
+use transverse_mercator::TransverseMercator;
 use utils::ToMm;
 
-/// Calculate the meridional radius of curvature
-#[allow(non_snake_case)]
-fn curvature(a: f64, f0: f64, e2: f64, lat: f64) -> f64 {
-    a * f0 * (1. - e2) * (1. - e2 * lat.sin().powi(2)).powf(-1.5)
-}
-
 /// Perform Longitude, Latitude to ETRS89 conversion
 ///
 /// # Examples
@@ -68,42 +61,12 @@ fn curvature(a: f64, f0: f64, e2: f64, lat: f64) -> f64 {
 /// assert_eq!((651307.003, 313255.686), convert_etrs89(&1.716073973, &52.658007833).unwrap());
 #[allow(non_snake_case)]
 // See Annexe B (p23) of the transformation user guide for instructions
-pub fn convert_etrs89(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_etrs89(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ConversionError> {
     // Input is restricted to the UK bounding box
-    // Convert bounds-checked input to degrees, or return an Err
-    let lon_1: f64 = check(*longitude, (MIN_LONGITUDE, MAX_LONGITUDE))?.to_radians();
-    let lat_1: f64 = check(*latitude, (MIN_LATITUDE, MAX_LATITUDE))?.to_radians();
-    // ellipsoid squared eccentricity constant
-    let e2 = (GRS80_SEMI_MAJOR.powi(2) - GRS80_SEMI_MINOR.powi(2)) / GRS80_SEMI_MAJOR.powi(2);
-    let n = (GRS80_SEMI_MAJOR - GRS80_SEMI_MINOR) / (GRS80_SEMI_MAJOR + GRS80_SEMI_MINOR);
-    let phi = lat_1;
-    let lambda = lon_1;
-
-    let sp2 = phi.sin().powi(2);
-    let nu = GRS80_SEMI_MAJOR * F0 * (1. - e2 * sp2).powf(-0.5); // v
-    let rho = GRS80_SEMI_MAJOR * F0 * (1. - e2) * (1. - e2 * sp2).powf(-1.5);
-    let eta2 = nu / rho - 1.;
-
-    let m = compute_m(&phi, &GRS80_SEMI_MINOR, &n);
-
-    let cp = phi.cos();
-    let sp = phi.sin();
-    let tp = phi.tan();
-    let tp2 = tp.powi(2);
-    let tp4 = tp.powi(4);
-
-    let I = m + N0;
-    let II = nu / 2. * sp * cp;
-    let III = nu / 24. * sp * cp.powi(3) * (5. - tp2 + 9. * eta2);
-    let IIIA = nu / 720. * sp * cp.powi(5) * (61. - 58. * tp2 + tp4);
-
-    let IV = nu * cp;
-    let V = nu / 6. * cp.powi(3) * (nu / rho - tp2);
-    let VI = nu / 120. * cp.powi(5) * (5. - 18. * tp2 + tp4 + 14. * eta2 - 58. * tp2 * eta2);
-
-    let l = lambda - LAM0;
-    let north = I + II * l.powi(2) + III * l.powi(4) + IIIA * l.powi(6);
-    let east = E0 + IV * l + V * l.powi(3) + VI * l.powi(5);
+    // Bounds-check input, or return an Err
+    let lon_1: f64 = check(*longitude, (MIN_LONGITUDE, MAX_LONGITUDE), Axis::Longitude)?;
+    let lat_1: f64 = check(*latitude, (MIN_LATITUDE, MAX_LATITUDE), Axis::Latitude)?;
+    let (east, north) = TransverseMercator::NATIONAL_GRID_ETRS89.forward(&lon_1, &lat_1)?;
     Ok((east.round_to_mm(), north.round_to_mm()))
 }
 
@@ -115,10 +78,12 @@ pub fn convert_etrs89(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ()>
 /// use lonlat_bng::convert_ETRS89_to_OSGB36
 /// assert_eq!((651409.792, 313177.448), convert_ETRS89_to_OSGB36(&651307.003, &313255.686).unwrap());
 #[allow(non_snake_case)]
-pub fn convert_etrs89_to_osgb36(eastings: &f64, northings: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_etrs89_to_osgb36(eastings: &f64,
+                                northings: &f64)
+                                -> Result<(f64, f64), ConversionError> {
     // ensure that we're within the boundaries
-    check(*eastings, (0.000, MAX_EASTING))?;
-    check(*northings, (0.000, MAX_NORTHING))?;
+    check(*eastings, (0.000, MAX_EASTING), Axis::Easting)?;
+    check(*northings, (0.000, MAX_NORTHING), Axis::Northing)?;
     // obtain OSTN02 corrections, and incorporate
     let (e_shift, n_shift, _) = ostn15_shifts(&eastings, &northings)?;
     Ok(((eastings + e_shift).round_to_mm(), (northings + n_shift).round_to_mm()))
@@ -133,7 +98,7 @@ pub fn convert_etrs89_to_osgb36(eastings: &f64, northings: &f64) -> Result<(f64,
 /// use lonlat_bng::convert_osgb36
 /// assert_eq!((651409.792, 313177.448), convert_etrs89(&1.716073973, &52.658007833).unwrap());
 #[allow(non_snake_case)]
-pub fn convert_osgb36(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_osgb36(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ConversionError> {
     // convert input to ETRS89
     let (eastings, northings) = convert_etrs89(longitude, latitude)?;
     // obtain OSTN02 corrections, and incorporate
@@ -141,119 +106,182 @@ pub fn convert_osgb36(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ()>
     Ok(((eastings + e_shift).round_to_mm(), (northings + n_shift).round_to_mm()))
 }
 
-// Intermediate calculation used for lon, lat to ETRS89 and reverse conversion
-fn compute_m(phi: &f64, b: &f64, n: &f64) -> f64 {
-    let p_plus = *phi + PHI0;
-    let p_minus = *phi - PHI0;
+/// Perform ETRS89 to OSGB36 conversion, including ETRS89 ellipsoidal height to
+/// Ordnance Datum Newlyn (ODN) orthometric height, using OSTN15 / OSGM15 data
+///
+/// Returns Eastings, Northings, and ODN height
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::convert_etrs89_to_osgb36_3d
+/// assert_eq!((651409.804, 313177.450, 63.822), convert_etrs89_to_osgb36_3d(&651307.003, &313255.686, &108.050).unwrap());
+pub fn convert_etrs89_to_osgb36_3d(eastings: &f64,
+                                   northings: &f64,
+                                   height: &f64)
+                                   -> Result<(f64, f64, f64), ConversionError> {
+    // ensure that we're within the boundaries
+    check(*eastings, (0.000, MAX_EASTING), Axis::Easting)?;
+    check(*northings, (0.000, MAX_NORTHING), Axis::Northing)?;
+    check(*height, (f64::MIN, f64::MAX), Axis::Height)?;
+    // the third OSTN15 shift is the geoid height, which is subtracted from the ellipsoidal height
+    let (e_shift, n_shift, g_shift) = ostn15_shifts(&eastings, &northings)?;
+    Ok(((eastings + e_shift).round_to_mm(),
+        (northings + n_shift).round_to_mm(),
+        (height - g_shift).round_to_mm()))
+}
 
-    *b * F0 *
-    ((1. + *n * (1. + 5. / 4. * *n * (1. + *n))) * p_minus -
-     3. * *n * (1. + *n * (1. + 7. / 8. * *n)) * p_minus.sin() * p_plus.cos() +
-     (15. / 8. * *n * (*n * (1. + *n))) * (2. * p_minus).sin() * (2. * p_plus).cos() -
-     35. / 24. * n.powi(3) * (3. * p_minus).sin() * (3. * p_plus).cos())
+/// Perform Longitude, Latitude, and ETRS89 ellipsoidal height to OSGB36 and ODN height
+/// conversion, using OSTN15 / OSGM15 data
+///
+/// Returns Eastings, Northings, and ODN height
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::convert_osgb36_3d
+/// assert_eq!((651409.804, 313177.450, 63.822), convert_osgb36_3d(&1.716073973, &52.658007833, &108.050).unwrap());
+pub fn convert_osgb36_3d(longitude: &f64,
+                         latitude: &f64,
+                         height: &f64)
+                         -> Result<(f64, f64, f64), ConversionError> {
+    // convert input to ETRS89
+    let (eastings, northings) = convert_etrs89(longitude, latitude)?;
+    convert_etrs89_to_osgb36_3d(&eastings, &northings, height)
 }
 
-// Easting and Northing to Lon, Lat conversion using a Helmert transform
-// Note that either GRS80 or Airy 1830 ellipsoids can be passed
-#[allow(non_snake_case)]
+/// As [`convert_etrs89_to_osgb36_3d`](fn.convert_etrs89_to_osgb36_3d.html), but also returns
+/// the OSGM15 vertical datum to which the height refers
+///
+/// The datum is that of the grid node nearest to the point, as given by `flags`
+pub fn convert_etrs89_to_osgb36_3d_with_datum
+    (eastings: &f64,
+     northings: &f64,
+     height: &f64,
+     flags: &DatumFlags)
+     -> Result<(f64, f64, f64, VerticalDatum), ConversionError> {
+    check(*eastings, (0.000, MAX_EASTING), Axis::Easting)?;
+    check(*northings, (0.000, MAX_NORTHING), Axis::Northing)?;
+    check(*height, (f64::MIN, f64::MAX), Axis::Height)?;
+    let (e_shift, n_shift, g_shift, datum) =
+        ostn15_shifts_with_datum(&eastings, &northings, flags)?;
+    Ok(((eastings + e_shift).round_to_mm(),
+        (northings + n_shift).round_to_mm(),
+        (height - g_shift).round_to_mm(),
+        datum))
+}
+
+/// As [`convert_osgb36_3d`](fn.convert_osgb36_3d.html), but also returns the OSGM15
+/// vertical datum to which the height refers
+pub fn convert_osgb36_3d_with_datum(longitude: &f64,
+                                    latitude: &f64,
+                                    height: &f64,
+                                    flags: &DatumFlags)
+                                    -> Result<(f64, f64, f64, VerticalDatum), ConversionError> {
+    let (eastings, northings) = convert_etrs89(longitude, latitude)?;
+    convert_etrs89_to_osgb36_3d_with_datum(&eastings, &northings, height, flags)
+}
+
+// Easting and Northing to Lon, Lat conversion
+// Note that the National Grid projection on either the GRS80 or Airy 1830 ellipsoid can be passed
 fn convert_to_ll(eastings: &f64,
                  northings: &f64,
-                 ell_a: f64,
-                 ell_b: f64)
-                 -> Result<(f64, f64), ()> {
+                 projection: &TransverseMercator)
+                 -> Result<(f64, f64), ConversionError> {
     // ensure that we're within the boundaries
-    check(*eastings, (0.000, MAX_EASTING))?;
-    check(*northings, (0.000, MAX_NORTHING))?;
-    // ellipsoid squared eccentricity constant
-    let a = ell_a;
-    let b = ell_b;
-    let e2 = (a.powi(2) - b.powi(2)) / a.powi(2);
-    let n = (a - b) / (a + b);
-
-    let dN = *northings - N0;
-    let mut phi = PHI0 + dN / (a * F0);
-    let mut m = compute_m(&phi, &b, &n);
-    while (dN - m) >= 0.001 {
-        phi += (dN - m) / (a * F0);
-        m = compute_m(&phi, &b, &n);
-    }
-    let sp2 = phi.sin().powi(2);
-    let nu = a * F0 * (1. - e2 * sp2).powf(-0.5);
-    let rho = a * F0 * (1. - e2) * (1. - e2 * sp2).powf(-1.5);
-    let eta2 = nu / rho - 1.;
-
-    let tp = phi.tan();
-    let tp2 = tp.powi(2);
-    let tp4 = tp.powi(4);
-
-    let VII = tp / (2. * rho * nu);
-    let VIII = tp / (24. * rho * nu.powi(3)) * (5. + 3. * tp2 + eta2 - 9. * tp2 * eta2);
-    let IX = tp / (720. * rho * nu.powi(5)) * (61. + 90. * tp2 + 45. * tp4);
-
-    let sp = 1.0 / phi.cos();
-    let tp6 = tp4 * tp2;
-
-    let X = sp / nu;
-    let XI = sp / (6. * nu.powi(3)) * (nu / rho + 2. * tp2);
-    let XII = sp / (120. * nu.powi(5)) * (5. + 28. * tp2 + 24. * tp4);
-    let XIIA = sp / (5040. * nu.powi(7)) * (61. + 662. * tp2 + 1320. * tp4 + 720. * tp6);
-
-    let e = *eastings - E0;
-
-    phi = phi - VII * e.powi(2) + VIII * e.powi(4) - IX * e.powi(6);
-    let mut lambda = LAM0 + X * e - XI * e.powi(3) + XII * e.powi(5) - XIIA * e.powi(7);
-
-    phi = phi.to_degrees();
-    lambda = lambda.to_degrees();
-    Ok(round_to_eight(lambda, phi))
+    check(*eastings, (0.000, MAX_EASTING), Axis::Easting)?;
+    check(*northings, (0.000, MAX_NORTHING), Axis::Northing)?;
+    let (lon, lat) = projection.inverse(eastings, northings)?;
+    Ok(round_to_eight(lon, lat))
 }
 
 /// Convert ETRS89 coordinates to Lon, Lat
 #[allow(non_snake_case)]
-pub fn convert_etrs89_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_etrs89_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
     // ETRS89 uses the WGS84 / GRS80 ellipsoid constants
-    convert_to_ll(E, N, GRS80_SEMI_MAJOR, GRS80_SEMI_MINOR)
+    convert_to_ll(E, N, &TransverseMercator::NATIONAL_GRID_ETRS89)
 }
 
 /// Convert OSGB36 coordinates to Lon, Lat using OSTN15 data
 #[allow(non_snake_case)]
-pub fn convert_osgb36_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_osgb36_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
     // Apply reverse OSTN02 adustments
-    let epsilon = 0.009;
-    let (mut dx, mut dy, _) = ostn15_shifts(&E, &N)?;
-    let (mut x, mut y) = (E - dx, N - dy);
-    let (mut last_dx, mut last_dy) = (dx, dy);
-    let mut res;
-    loop {
-        res = ostn15_shifts(&x, &y)?;
-        dx = res.0;
-        dy = res.1;
-        x = E - dx;
-        y = N - dy;
-        // If the difference […] is more than 0.00010m (User Guide, p15)
-        // TODO: invert this logic
-        if (dx - last_dx).abs() < epsilon && (dy - last_dy).abs() < epsilon {
-            break;
-        }
-        last_dx = dx;
-        last_dy = dy;
-    }
-    let x = (E - dx).round_to_mm();
-    let y = (N - dy).round_to_mm();
+    // If the difference […] is more than 0.00010m (User Guide, p15)
+    // TODO: invert this logic
+    let (x, y) = reverse_ostn15(E, N, 0.009)?;
     // We've converted to ETRS89, so we need to use the WGS84/ GRS80 ellipsoid constants
-    convert_to_ll(&x, &y, GRS80_SEMI_MAJOR, GRS80_SEMI_MINOR)
+    convert_to_ll(&x, &y, &TransverseMercator::NATIONAL_GRID_ETRS89)
 }
 
 /// Convert OSGB36 coordinates to ETRS89 using OSTN15 data
 #[allow(non_snake_case)]
-pub fn convert_osgb36_to_etrs89(E: &f64, N: &f64) -> Result<(f64, f64), ()> {
+pub fn convert_osgb36_to_etrs89(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
     // Apply reverse OSTN15 adustments
-    let epsilon = 0.00001;
+    reverse_ostn15(E, N, 0.00001)
+}
+
+/// Convert OSGB36 coordinates and ODN height to ETRS89 coordinates and ellipsoidal height,
+/// using OSTN15 / OSGM15 data
+///
+/// The geoid height is taken at the converged ETRS89 position
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::convert_osgb36_to_etrs89_3d
+/// assert_eq!((651307.003, 313255.686, 108.050), convert_osgb36_to_etrs89_3d(&651409.804, &313177.450, &63.822).unwrap());
+#[allow(non_snake_case)]
+pub fn convert_osgb36_to_etrs89_3d(E: &f64,
+                                   N: &f64,
+                                   H: &f64)
+                                   -> Result<(f64, f64, f64), ConversionError> {
+    check(*H, (f64::MIN, f64::MAX), Axis::Height)?;
+    let (x, y) = reverse_ostn15(E, N, 0.00001)?;
+    let (_, _, g_shift) = ostn15_shifts(&x, &y)?;
+    Ok((x, y, (H + g_shift).round_to_mm()))
+}
+
+/// As [`convert_osgb36_to_etrs89_3d`](fn.convert_osgb36_to_etrs89_3d.html), but also returns
+/// the OSGM15 vertical datum to which the input height refers
+#[allow(non_snake_case)]
+pub fn convert_osgb36_to_etrs89_3d_with_datum
+    (E: &f64,
+     N: &f64,
+     H: &f64,
+     flags: &DatumFlags)
+     -> Result<(f64, f64, f64, VerticalDatum), ConversionError> {
+    check(*H, (f64::MIN, f64::MAX), Axis::Height)?;
+    let (x, y) = reverse_ostn15(E, N, 0.00001)?;
+    let (_, _, g_shift, datum) = ostn15_shifts_with_datum(&x, &y, flags)?;
+    Ok((x, y, (H + g_shift).round_to_mm(), datum))
+}
+
+/// Convert OSGB36 coordinates and ODN height to Lon, Lat and ETRS89 ellipsoidal height,
+/// using OSTN15 / OSGM15 data
+#[allow(non_snake_case)]
+pub fn convert_osgb36_to_ll_3d(E: &f64,
+                               N: &f64,
+                               H: &f64)
+                               -> Result<(f64, f64, f64), ConversionError> {
+    let (x, y, h) = convert_osgb36_to_etrs89_3d(E, N, H)?;
+    let (lon, lat) = convert_to_ll(&x, &y, &TransverseMercator::NATIONAL_GRID_ETRS89)?;
+    Ok((lon, lat, h))
+}
+
+// Iteratively remove OSTN15 shifts from OSGB36 coordinates, to obtain ETRS89 coordinates
+// Iteration stops when successive shifts differ by less than epsilon
+#[allow(non_snake_case)]
+fn reverse_ostn15(E: &f64, N: &f64, epsilon: f64) -> Result<(f64, f64), ConversionError> {
     let (mut dx, mut dy, _) = ostn15_shifts(&E, &N)?;
     let (mut x, mut y) = (E - dx, N - dy);
     let (mut last_dx, mut last_dy) = (dx, dy);
     let mut res;
+    let mut iterations = 0;
     loop {
+        iterations += 1;
+        if iterations > MAX_ITERATIONS {
+            return Err(ConversionError::NoConvergence);
+        }
         res = ostn15_shifts(&x, &y)?;
         dx = res.0;
         dy = res.1;
@@ -281,94 +309,21 @@ pub fn convert_osgb36_to_etrs89(E: &f64, N: &f64) -> Result<(f64, f64), ()> {
 /// assert_eq!((516276.000, 173141.000), convert_bng(&-0.32824866, &51.44533267).unwrap());
 #[allow(non_snake_case)]
 #[allow(dead_code)]
-pub fn convert_bng(longitude: &f64, latitude: &f64) -> Result<(c_double, c_double), ()> {
+pub fn convert_bng(longitude: &f64,
+                   latitude: &f64)
+                   -> Result<(c_double, c_double), ConversionError> {
     // input is restricted to the UK bounding box
-    // Convert bounds-checked input to degrees, or return an Err
-    let lon_1: f64 = check(*longitude, (MIN_LONGITUDE, MAX_LONGITUDE))?.to_radians();
-    let lat_1: f64 = check(*latitude, (MIN_LATITUDE, MAX_LATITUDE))?.to_radians();
-    // The GRS80 semi-major and semi-minor axes used for WGS84 (m)
-    let a_1 = GRS80_SEMI_MAJOR;
-    let b_1 = GRS80_SEMI_MINOR;
-    // The eccentricity (squared) of the GRS80 ellipsoid
-    let e2_1 = 1. - (b_1.powi(2)) / (a_1.powi(2));
-    // Transverse radius of curvature
-    let nu_1 = a_1 / (1. - e2_1 * lat_1.sin().powi(2)).sqrt();
+    // Bounds-check input, or return an Err
+    let lon_1: f64 = check(*longitude, (MIN_LONGITUDE, MAX_LONGITUDE), Axis::Longitude)?;
+    let lat_1: f64 = check(*latitude, (MIN_LATITUDE, MAX_LATITUDE), Axis::Latitude)?;
+    // Perform Helmert transform (to go between GRS80 and Airy 1830)
     // Third spherical coordinate is 0, in this case
-    let H: f64 = 0.;
-    let x_1 = (nu_1 + H) * lat_1.cos() * lon_1.cos();
-    let y_1 = (nu_1 + H) * lat_1.cos() * lon_1.sin();
-    let z_1 = ((1. - e2_1) * nu_1 + H) * lat_1.sin();
-
-    // Perform Helmert transform (to go between Airy 1830 (_1) and GRS80 (_2))
-    // The translations along x, y, z axes respectively
-    let tx = TX;
-    let ty = TY;
-    let tz = TZ;
-    // The rotations along x, y, z respectively, in seconds
-    let rxs = RXS;
-    let rys = RYS;
-    let rzs = RZS;
-    // In radians
-    let rx = rxs * PI / (180. * 3600.);
-    let ry = rys * PI / (180. * 3600.);
-    let rz = rzs * PI / (180. * 3600.);
-
-    // TODO solve this for all lat and lon using matrices in an intermediate step?
-    let x_2 = tx + (1. + S) * x_1 + -rz * y_1 + ry * z_1;
-    let y_2 = ty + rz * x_1 + (1. + S) * y_1 + -rx * z_1;
-    let z_2 = tz + -ry * x_1 + rx * y_1 + (1. + S) * z_1;
-
-    // The Airy 1830 semi-major and semi-minor axes used for OSGB36 (m)
-    let a = AIRY_1830_SEMI_MAJOR;
-    let b = AIRY_1830_SEMI_MINOR;
-    // The eccentricity of the Airy 1830 ellipsoid
-    let e2 = 1. - b.powi(2) / a.powi(2);
-    let p = (x_2.powi(2) + y_2.powi(2)).sqrt();
-    // Initial value
-    let mut lat = z_2.atan2((p * (1. - e2)));
-    let mut latold = 2. * PI;
-    // this is cheating, but not sure how else to initialise nu
-    let mut nu: f64 = 1.;
-    // Latitude is obtained by iterative procedure
-    while (lat - latold).abs() > (10. as f64).powi(-16) {
-        mem::swap(&mut lat, &mut latold);
-        nu = a / (1. - e2 * latold.sin().powi(2)).sqrt();
-        lat = (z_2 + e2 * nu * latold.sin()).atan2(p);
-    }
-    let lon = y_2.atan2(x_2);
-    // Latitude of true origin (radians)
-    let lat0 = 49. * PI / 180.;
-    // Longitude of true origin and central meridian (radians)
-    let lon0 = -2. * PI / 180.;
-    // Northing & easting of true origin (m)
-    let n = (a - b) / (a + b);
-    // Meridional radius of curvature
-    let rho = curvature(a, F0, e2, lat);
-    let eta2 = nu * F0 / rho - 1.;
-
-    let M1 = (1. + n + (5. / 4.) * n.powi(2) + (5. / 4.) * n.powi(3)) * (lat - lat0);
-    let M2 = (3. * n + 3. * n.powi(2) + (21. / 8.) * n.powi(3)) *
-             ((lat.sin() * lat0.cos()) - (lat.cos() * lat0.sin())).ln_1p().exp_m1() *
-             (lat + lat0).cos();
-    let M3 = ((15. / 8.) * n.powi(2) + (15. / 8.) * n.powi(3)) * (2. * (lat - lat0)).sin() *
-             (2. * (lat + lat0)).cos();
-    let M4 = (35. / 24.) * n.powi(3) * (3. * (lat - lat0)).sin() * (3. * (lat + lat0)).cos();
-    let M = b * F0 * (M1 - M2 + M3 - M4);
-
-    let I = M + N0;
-    let II = nu * F0 * lat.sin() * lat.cos() / 2.;
-    let III = nu * F0 * lat.sin() * lat.cos().powi(3) * (5. - lat.tan().powi(2) + 9. * eta2) / 24.;
-    let IIIA = nu * F0 * lat.sin() * lat.cos().powi(5) *
-               (61. - 58. * lat.tan().powi(2) + lat.tan().powi(4)) / 720.;
-    let IV = nu * F0 * lat.cos();
-    let V = nu * F0 * lat.cos().powi(3) * (nu / rho - lat.tan().powi(2)) / 6.;
-    let VI = nu * F0 * lat.cos().powi(5) *
-             (5. - 18. * lat.tan().powi(2) + lat.tan().powi(4) + 14. * eta2 -
-              58. * eta2 * lat.tan().powi(2)) / 120.;
-    let N = I + II * (lon - lon0).powi(2) + III * (lon - lon0).powi(4) +
-            IIIA * (lon - lon0).powi(6);
-    let E = E0 + IV * (lon - lon0) + V * (lon - lon0).powi(3) + VI * (lon - lon0).powi(5);
-
+    let (lon, lat, _) = Helmert::etrs89_to_osgb36().transform_geodetic(&lon_1,
+                                        &lat_1,
+                                        &0.,
+                                        &Ellipsoid::GRS80,
+                                        &Ellipsoid::AIRY_1830)?;
+    let (E, N) = TransverseMercator::NATIONAL_GRID.forward(&lon, &lat)?;
     Ok((E.round_to_mm(), N.round_to_mm()))
 }
 
@@ -383,119 +338,65 @@ pub fn convert_bng(longitude: &f64, latitude: &f64) -> Result<(c_double, c_doubl
 /// assert_eq!((-0.328248, 51.44534), convert_lonlat(&516276, &173141));
 #[allow(non_snake_case)]
 #[allow(dead_code)]
-pub fn convert_lonlat(easting: &f64, northing: &f64) -> Result<(f64, f64), ()> {
-    // The Airy 1830 semi-major and semi-minor axes used for OSGB36 (m)
-    let a = AIRY_1830_SEMI_MAJOR;
-    let b = AIRY_1830_SEMI_MINOR;
-    // Scale factor on the central meridian
-    // let F0: f64 = 0.9996012717;
-    // Latitude of true origin (radians)
-    let lat0 = 49. * PI / 180.;
-    // Longitude of true origin and central meridian (radians)
-    let lon0 = -2. * PI / 180.;
-    // Northing & easting of true origin (m)
-    // Eccentricity squared
-    let e2 = 1. - b.powi(2) / a.powi(2);
-    let n = (a - b) / (a + b);
-
-    let mut lat = lat0;
-    let mut M: f64 = 0.0;
-    while (*northing - N0 - M) >= 0.00001 {
-        lat += (*northing - N0 - M) / (a * F0);
-        let M1 = (1. + n + (5. / 4.) * n.powi(3) + (5. / 4.) * n.powi(3)) * (lat - lat0);
-        let M2 = (3. * n + 3. * n.powi(2) + (21. / 8.) * n.powi(3)) *
-                 ((lat.sin() * lat0.cos()) - (lat.cos() * lat0.sin())).ln_1p().exp_m1() *
-                 (lat + lat0).cos();
-        let M3 = ((15. / 8.) * n.powi(2) + (15. / 8.) * n.powi(3)) * (2. * (lat - lat0)).sin() *
-                 (2. * (lat + lat0)).cos();
-        let M4 = (35. / 24.) * n.powi(3) * (3. * (lat - lat0)).sin() * (3. * (lat + lat0)).cos();
-        // Meridional arc!
-        M = b * F0 * (M1 - M2 + M3 - M4);
-    }
-    // Transverse radius of curvature
-    let nu = a * F0 / (1. - e2 * lat.sin().powi(2)).sqrt();
-    // Meridional radius of curvature
-    let rho = curvature(a, F0, e2, lat);
-    let eta2 = nu / rho - 1.;
-
-    let secLat = 1. / lat.cos();
-    let VII = lat.tan() / (2. * rho * nu);
-    let VIII = lat.tan() / (24. * rho * nu.powi(3)) *
-               (5. + 3. * lat.tan().powi(2) + eta2 - 9. * lat.tan().powi(2) * eta2);
-    let IX = lat.tan() / (720. * rho * nu.powi(5)) *
-             (61. + 90. * lat.tan().powi(2) + 45. * lat.tan().powi(4));
-    let X = secLat / nu;
-    let XI = secLat / (6. * nu.powi(3)) * (nu / rho + 2. * lat.tan().powi(2));
-    let XII = secLat / (120. * nu.powi(5)) *
-              (5. + 28. * lat.tan().powi(2) + 24. * lat.tan().powi(4));
-    let XIIA = secLat / (5040. * nu.powi(7)) *
-               (61. + 662. * lat.tan().powi(2) + 1320. * lat.tan().powi(4) +
-                720. * lat.tan().powi(6));
-    let dE = *easting - E0;
+pub fn convert_lonlat(easting: &f64, northing: &f64) -> Result<(f64, f64), ConversionError> {
     // These are on the wrong ellipsoid currently: Airy1830 (Denoted by _1)
-    let lat_1 = lat - VII * dE.powi(2) + VIII * dE.powi(4) - IX * dE.powi(6);
-    let lon_1 = lon0 + X * dE - XI * dE.powi(3) + XII * dE.powi(5) - XIIA * dE.powi(7);
-
-    // We want to convert to the GRS80 ellipsoid
-    // First, convert to cartesian from spherical polar coordinates
-    let H = 0.;
-    let x_1 = (nu / F0 + H) * lat_1.cos() * lon_1.cos();
-    let y_1 = (nu / F0 + H) * lat_1.cos() * lon_1.sin();
-    let z_1 = ((1. - e2) * nu / F0 + H) * lat_1.sin();
-
-    // Perform Helmert transform (to go between Airy 1830 (_1) and GRS80 (_2))
-    let minus_s = -S; // The scale factor -1
-    // The translations along x, y, z axes respectively
-    let tx = TX.abs();
-    let ty = TY * -1.;
-    let tz = TZ.abs();
-    // The rotations along x, y, z respectively, in seconds
-    let rxs = RXS * -1.;
-    let rys = RYS * -1.;
-    let rzs = RZS * -1.;
-
-    let rx = rxs * PI / (180. * 3600.);
-    let ry = rys * PI / (180. * 3600.);
-    let rz = rzs * PI / (180. * 3600.); // In radians
-    let x_2 = tx + (1. + minus_s) * x_1 + (-rz) * y_1 + (ry) * z_1;
-    let y_2 = ty + (rz) * x_1 + (1. + minus_s) * y_1 + (-rx) * z_1;
-    let z_2 = tz + (-ry) * x_1 + (rx) * y_1 + (1. + minus_s) * z_1;
-
-    // Back to spherical polar coordinates from cartesian
-    // Need some of the characteristics of the new ellipsoid
-    // The GRS80 semi-major and semi-minor axes used for WGS84(m)
-    let a_2 = GRS80_SEMI_MAJOR;
-    let b_2 = GRS80_SEMI_MINOR;
-    // The eccentricity of the GRS80 ellipsoid
-    let e2_2 = 1. - b_2.powi(2) / a_2.powi(2);
-    let p = (x_2.powi(2) + y_2.powi(2)).sqrt();
-
-    // Lat is obtained by iterative procedure
-    // Initial value
-    let mut lat = z_2.atan2((p * (1. - e2_2)));
-    let mut latold = 2. * PI;
-    let mut nu_2: f64;
-    while (lat - latold).abs() > (10. as f64).powi(-16) {
-        mem::swap(&mut lat, &mut latold);
-        nu_2 = a_2 / (1. - e2_2 * latold.sin().powi(2)).sqrt();
-        lat = (z_2 + e2_2 * nu_2 * latold.sin()).atan2(p);
-    }
+    let (lon_1, lat_1) = TransverseMercator::NATIONAL_GRID.inverse(easting, northing)?;
 
-    let mut lon = y_2.atan2(x_2);
-    lat = lat * 180. / PI;
-    lon = lon * 180. / PI;
+    // We want to convert to the GRS80 ellipsoid, using the published OSGB36 to ETRS89 parameters
+    // Third spherical coordinate is 0, in this case
+    let (lon, lat, _) = Helmert::osgb36_to_etrs89().transform_geodetic(&lon_1,
+                                        &lat_1,
+                                        &0.,
+                                        &Ellipsoid::AIRY_1830,
+                                        &Ellipsoid::GRS80)?;
     Ok(round_to_eight(lon, lat))
 }
 
 /// Convert Web Mercator (from Google Maps or Bing Maps) to WGS84
 // from https://alastaira.wordpress.com/2011/01/23/the-google-maps-bing-maps-spherical-mercator-projection/
-pub fn convert_epsg3857_to_wgs84(x: &f64, y: &f64) -> Result<(f64, f64), ()> {
-    let lon = (x / 20037508.34) * 180.;
-    let mut lat = (y / 20037508.34) * 180.;
+pub fn convert_epsg3857_to_wgs84(x: &f64, y: &f64) -> Result<(f64, f64), ConversionError> {
+    let lon = (x / WEB_MERCATOR_EXTENT) * 180.;
+    let mut lat = (y / WEB_MERCATOR_EXTENT) * 180.;
     lat = 180. / PI * (2. * (lat * PI / 180.).exp().atan() - PI / 2.);
     Ok((lon, lat))
 }
 
+/// Convert WGS84 to Web Mercator (for Google Maps or Bing Maps)
+///
+/// Latitudes beyond ±85.0511°, which lie outside the square Web Mercator map, are rejected
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::convert_wgs84_to_epsg3857;
+/// assert_eq!((-626172.136, 6887893.493),
+///            convert_wgs84_to_epsg3857(&-5.625000000783013, &52.48278022732355).unwrap());
+/// ```
+pub fn convert_wgs84_to_epsg3857(longitude: &f64,
+                                 latitude: &f64)
+                                 -> Result<(f64, f64), ConversionError> {
+    let lon = check(*longitude, (-180., 180.), Axis::Longitude)?;
+    let lat = check(*latitude,
+                    (-MAX_WEB_MERCATOR_LATITUDE, MAX_WEB_MERCATOR_LATITUDE),
+                    Axis::Latitude)?;
+    let x = lon * WEB_MERCATOR_EXTENT / 180.;
+    let y = ((90. + lat) * PI / 360.).tan().ln() / (PI / 180.) * WEB_MERCATOR_EXTENT / 180.;
+    Ok((x.round_to_mm(), y.round_to_mm()))
+}
+
+/// Convert OSGB36 Eastings and Northings to Web Mercator, using OSTN15 data
+#[allow(non_snake_case)]
+pub fn convert_osgb36_to_epsg3857(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
+    let (lon, lat) = convert_osgb36_to_ll(E, N)?;
+    convert_wgs84_to_epsg3857(&lon, &lat)
+}
+
+/// Convert Web Mercator coordinates to OSGB36 Eastings and Northings, using OSTN15 data
+pub fn convert_epsg3857_to_osgb36(x: &f64, y: &f64) -> Result<(f64, f64), ConversionError> {
+    let (lon, lat) = convert_epsg3857_to_wgs84(x, y)?;
+    convert_osgb36(&lon, &lat)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -507,6 +408,18 @@ mod tests {
     use super::convert_bng;
     use super::convert_lonlat;
     use super::convert_epsg3857_to_wgs84;
+    use super::convert_wgs84_to_epsg3857;
+    use super::convert_osgb36_to_epsg3857;
+    use super::convert_epsg3857_to_osgb36;
+    use super::convert_osgb36_3d;
+    use super::convert_etrs89_to_osgb36_3d;
+    use super::convert_osgb36_to_etrs89_3d;
+    use super::convert_osgb36_to_ll_3d;
+    use super::convert_osgb36_3d_with_datum;
+    use datum::{DatumFlags, VerticalDatum};
+    use std::io::Cursor;
+    use errors::{Axis, ConversionError};
+    use std::f64;
 
     #[test]
     fn test_gmaps_to_wgs() {
@@ -517,6 +430,36 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_wgs_to_gmaps() {
+        let (x, y) = convert_wgs84_to_epsg3857(&-5.625000000783013, &52.48278022732355).unwrap();
+        assert_eq!((-626172.136, 6887893.493), (x, y));
+        assert_eq!((0., 0.), convert_wgs84_to_epsg3857(&0., &0.).unwrap());
+        let (lon, lat) = convert_epsg3857_to_wgs84(&x, &y).unwrap();
+        assert!((lon - -5.625000000783013).abs() < 0.00000001);
+        assert!((lat - 52.48278022732355).abs() < 0.00000001);
+    }
+
+    #[test]
+    fn test_web_mercator_bad_lat() {
+        match convert_wgs84_to_epsg3857(&0., &85.06) {
+            Err(ConversionError::OutOfBounds { axis: Axis::Latitude, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(convert_wgs84_to_epsg3857(&0., &-85.0511).is_ok());
+    }
+
+    #[test]
+    fn test_osgb36_web_mercator() {
+        let (lon, lat) = convert_osgb36_to_ll(&651409.792, &313177.448).unwrap();
+        let expected = convert_wgs84_to_epsg3857(&lon, &lat).unwrap();
+        let (x, y) = convert_osgb36_to_epsg3857(&651409.792, &313177.448).unwrap();
+        assert_eq!(expected, (x, y));
+        let (e, n) = convert_epsg3857_to_osgb36(&x, &y).unwrap();
+        assert!((e - 651409.792).abs() < 0.002);
+        assert!((n - 313177.448).abs() < 0.002);
+    }
+
     #[test]
     fn test_convert_osgb36_to_ll() {
         // Caister Water Tower, with OSTN15 corrections applied. See p23
@@ -565,6 +508,49 @@ mod tests {
                    convert_etrs89_to_osgb36(&eastings, &northings).unwrap());
     }
 
+    #[test]
+    fn test_osgb36_3d_conversion() {
+        // Caister Water Tower, p20–23: ETRS89 ellipsoidal height 108.050, geoid height 44.228
+        let expected = (651409.804, 313177.450, 63.822);
+        assert_eq!(expected,
+                   convert_osgb36_3d(&1.716073973, &52.658007833, &108.050).unwrap());
+        assert_eq!(expected,
+                   convert_etrs89_to_osgb36_3d(&651307.003, &313255.686, &108.050).unwrap());
+    }
+
+    #[test]
+    fn test_osgb36_to_etrs89_3d_conversion() {
+        // Caister Water Tower, p20–23, in reverse
+        assert_eq!((651307.003, 313255.686, 108.050),
+                   convert_osgb36_to_etrs89_3d(&651409.804, &313177.450, &63.822).unwrap());
+        assert_eq!((1.71607397, 52.65800783, 108.050),
+                   convert_osgb36_to_ll_3d(&651409.804, &313177.450, &63.822).unwrap());
+    }
+
+    #[test]
+    fn test_osgb36_3d_conversion_with_datum() {
+        // Caister Water Tower's nearest grid node is (651, 313)
+        let data = "220065,651000,313000,102.787,-78.242,44.236,1";
+        let flags = DatumFlags::from_reader(Cursor::new(data)).unwrap();
+        let res = convert_osgb36_3d_with_datum(&1.716073973, &52.658007833, &108.050, &flags)
+            .unwrap();
+        assert_eq!(VerticalDatum::Newlyn, res.3);
+        // a point whose nearest node has no flag
+        assert_eq!(Err(ConversionError::MissingDatumFlag {
+                       easting_index: 652,
+                       northing_index: 313,
+                   }),
+                   convert_osgb36_3d_with_datum(&1.72, &52.658007833, &108.050, &flags));
+    }
+
+    #[test]
+    fn test_bad_height() {
+        match convert_osgb36_3d(&1.716073973, &52.658007833, &f64::NAN) {
+            Err(ConversionError::NonFinite { axis: Axis::Height, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_bad_max_easting() {
@@ -585,8 +571,9 @@ mod tests {
 
     #[test]
     fn test_bng_conversion() {
-        // verified to be correct at http://www.bgs.ac.uk/data/webservices/convertForm.cfm
-        assert_eq!((516275.973, 173141.092),
+        // The projection reproduces the worked example in Annexe C of the OS guide (see
+        // transverse_mercator::tests). The easting was 516275.973 until the V term was corrected
+        assert_eq!((516275.971, 173141.092),
                    convert_bng(&-0.32824866, &51.44533267).unwrap());
     }
 
@@ -0,0 +1,222 @@
+//! This module provides strongly typed coordinates for each supported coordinate reference system
+//!
+//! Conversions between them are provided by `TryFrom`, and call the corresponding
+//! functions in the conversions module, so coordinates from different systems can't be mixed up.
+use std::convert::TryFrom;
+
+use conversions::{convert_epsg3857_to_wgs84, convert_etrs89, convert_etrs89_to_ll,
+                  convert_etrs89_to_osgb36, convert_osgb36, convert_osgb36_to_etrs89,
+                  convert_osgb36_to_ll, convert_wgs84_to_epsg3857};
+use errors::ConversionError;
+
+/// A WGS84 / ETRS89 longitude and latitude, in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LonLat {
+    lon: f64,
+    lat: f64,
+}
+
+impl LonLat {
+    pub fn new(lon: f64, lat: f64) -> LonLat {
+        LonLat { lon: lon, lat: lat }
+    }
+
+    pub fn lon(&self) -> f64 {
+        self.lon
+    }
+
+    pub fn lat(&self) -> f64 {
+        self.lat
+    }
+}
+
+/// An ETRS89 Transverse Mercator easting and northing, in metres
+///
+/// These are the intermediate grid coordinates to which OSTN15 shifts are applied,
+/// and are **not** National Grid coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Etrs89Grid {
+    easting: f64,
+    northing: f64,
+}
+
+impl Etrs89Grid {
+    pub fn new(easting: f64, northing: f64) -> Etrs89Grid {
+        Etrs89Grid {
+            easting: easting,
+            northing: northing,
+        }
+    }
+
+    pub fn easting(&self) -> f64 {
+        self.easting
+    }
+
+    pub fn northing(&self) -> f64 {
+        self.northing
+    }
+}
+
+/// An OSGB36 British National Grid easting and northing, in metres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Osgb36Grid {
+    easting: f64,
+    northing: f64,
+}
+
+impl Osgb36Grid {
+    pub fn new(easting: f64, northing: f64) -> Osgb36Grid {
+        Osgb36Grid {
+            easting: easting,
+            northing: northing,
+        }
+    }
+
+    pub fn easting(&self) -> f64 {
+        self.easting
+    }
+
+    pub fn northing(&self) -> f64 {
+        self.northing
+    }
+}
+
+/// A Web Mercator (EPSG:3857) x and y, in metres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WebMercator {
+    x: f64,
+    y: f64,
+}
+
+impl WebMercator {
+    pub fn new(x: f64, y: f64) -> WebMercator {
+        WebMercator { x: x, y: y }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+impl TryFrom<LonLat> for Etrs89Grid {
+    type Error = ConversionError;
+
+    fn try_from(point: LonLat) -> Result<Self, Self::Error> {
+        let (easting, northing) = convert_etrs89(&point.lon, &point.lat)?;
+        Ok(Etrs89Grid::new(easting, northing))
+    }
+}
+
+impl TryFrom<LonLat> for Osgb36Grid {
+    type Error = ConversionError;
+
+    fn try_from(point: LonLat) -> Result<Self, Self::Error> {
+        let (easting, northing) = convert_osgb36(&point.lon, &point.lat)?;
+        Ok(Osgb36Grid::new(easting, northing))
+    }
+}
+
+impl TryFrom<Etrs89Grid> for LonLat {
+    type Error = ConversionError;
+
+    fn try_from(point: Etrs89Grid) -> Result<Self, Self::Error> {
+        let (lon, lat) = convert_etrs89_to_ll(&point.easting, &point.northing)?;
+        Ok(LonLat::new(lon, lat))
+    }
+}
+
+impl TryFrom<Osgb36Grid> for LonLat {
+    type Error = ConversionError;
+
+    fn try_from(point: Osgb36Grid) -> Result<Self, Self::Error> {
+        let (lon, lat) = convert_osgb36_to_ll(&point.easting, &point.northing)?;
+        Ok(LonLat::new(lon, lat))
+    }
+}
+
+impl TryFrom<Etrs89Grid> for Osgb36Grid {
+    type Error = ConversionError;
+
+    fn try_from(point: Etrs89Grid) -> Result<Self, Self::Error> {
+        let (easting, northing) = convert_etrs89_to_osgb36(&point.easting, &point.northing)?;
+        Ok(Osgb36Grid::new(easting, northing))
+    }
+}
+
+impl TryFrom<Osgb36Grid> for Etrs89Grid {
+    type Error = ConversionError;
+
+    fn try_from(point: Osgb36Grid) -> Result<Self, Self::Error> {
+        let (easting, northing) = convert_osgb36_to_etrs89(&point.easting, &point.northing)?;
+        Ok(Etrs89Grid::new(easting, northing))
+    }
+}
+
+impl TryFrom<WebMercator> for LonLat {
+    type Error = ConversionError;
+
+    fn try_from(point: WebMercator) -> Result<Self, Self::Error> {
+        let (lon, lat) = convert_epsg3857_to_wgs84(&point.x, &point.y)?;
+        Ok(LonLat::new(lon, lat))
+    }
+}
+
+impl TryFrom<LonLat> for WebMercator {
+    type Error = ConversionError;
+
+    fn try_from(point: LonLat) -> Result<Self, Self::Error> {
+        let (x, y) = convert_wgs84_to_epsg3857(&point.lon, &point.lat)?;
+        Ok(WebMercator::new(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use errors::Axis;
+
+    #[test]
+    fn test_lonlat_to_etrs89_grid() {
+        // Caister Water Tower, see p20
+        let point = LonLat::new(1.716073973, 52.658007833);
+        let grid = Etrs89Grid::try_from(point).unwrap();
+        assert_eq!(651307.003, grid.easting());
+        assert_eq!(313255.686, grid.northing());
+    }
+
+    #[test]
+    fn test_etrs89_grid_to_lonlat() {
+        let grid = Etrs89Grid::new(651307.003, 313255.686);
+        let point = LonLat::try_from(grid).unwrap();
+        assert_eq!(LonLat::new(1.71607397, 52.65800783), point);
+    }
+
+    #[test]
+    fn test_webmercator_to_lonlat() {
+        let point = LonLat::try_from(WebMercator::new(-626172.1357121646, 6887893.4928337997))
+            .unwrap();
+        assert_eq!(-5.625000000783013, point.lon());
+        assert_eq!(52.48278022732355, point.lat());
+    }
+
+    #[test]
+    fn test_lonlat_to_webmercator() {
+        let point = WebMercator::try_from(LonLat::new(-5.625000000783013, 52.48278022732355))
+            .unwrap();
+        assert_eq!(-626172.136, point.x());
+        assert_eq!(6887893.493, point.y());
+    }
+
+    #[test]
+    fn test_bad_lonlat() {
+        let point = LonLat::new(181., 51.44533267);
+        match Osgb36Grid::try_from(point) {
+            Err(ConversionError::OutOfBounds { axis: Axis::Longitude, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,547 @@
+//! This module provides a coordinate reference system type, and a transformer which
+//! chains the existing conversion functions together to convert between any two of them
+use std::collections::VecDeque;
+use std::fmt;
+
+use conversions::{convert_epsg3857_to_wgs84, convert_etrs89, convert_etrs89_to_ll,
+                  convert_etrs89_to_osgb36, convert_osgb36, convert_osgb36_3d,
+                  convert_osgb36_to_etrs89, convert_osgb36_to_ll, convert_osgb36_to_ll_3d,
+                  convert_wgs84_to_epsg3857};
+use errors::ConversionError;
+use utm::{convert_utm, convert_utm_to_ll, UtmZone};
+use irish::{convert_irish_grid, convert_irish_grid_to_ll, convert_itm, convert_itm_to_ll};
+use super::{convert_vec_direct, convert_vec_direct_3d, convert_vec_direct_status};
+
+/// A coordinate reference system supported by the crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Crs {
+    /// WGS84 longitude and latitude, in decimal degrees. This is treated as being identical to ETRS89
+    Wgs84,
+    /// ETRS89 longitude and latitude, in decimal degrees
+    Etrs89,
+    /// ETRS89 Transverse Mercator eastings and northings, using the National Grid projection
+    Etrs89Grid,
+    /// OSGB36 British National Grid eastings and northings
+    Osgb36,
+    /// Web Mercator (Google Maps, Bing Maps) x and y, in metres
+    WebMercator,
+    /// Irish Transverse Mercator eastings and northings
+    Itm,
+    /// Irish Grid eastings and northings
+    IrishGrid,
+    /// ETRS89 / UTM eastings and northings
+    Etrs89Utm(UtmZone),
+    /// WGS84 / UTM eastings and northings
+    Wgs84Utm(UtmZone),
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Crs::Etrs89Utm(zone) => return write!(f, "ETRS89 / UTM zone {}", zone),
+            Crs::Wgs84Utm(zone) => return write!(f, "WGS84 / UTM zone {}", zone),
+            Crs::Wgs84 => "WGS84",
+            Crs::Etrs89 => "ETRS89",
+            Crs::Etrs89Grid => "ETRS89 grid",
+            Crs::Osgb36 => "OSGB36",
+            Crs::WebMercator => "Web Mercator",
+            Crs::Itm => "Irish Transverse Mercator",
+            Crs::IrishGrid => "Irish Grid",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// EPSG codes which can be mapped to a Crs
+static SUPPORTED_EPSG: &'static [u32] = &[4326, 4258, 27700, 3857, 2157, 29903, 25829, 25830,
+                                           25831, 32629, 32630, 32631];
+
+// EPSG codes which carry heights: ETRS89 and WGS84 with ellipsoidal heights, and British
+// National Grid with ODN heights (27700 + 5701)
+static SUPPORTED_EPSG_3D: &'static [u32] = &[4326, 4258, 7405];
+
+impl Crs {
+    /// Look up the coordinate reference system for an EPSG code.
+    ///
+    /// Transformations are horizontal only, so the compound code 7405 (British National Grid +
+    /// ODN height) is unsupported here: converting it as 27700 would drop the heights. Use
+    /// [`transform_3d`](fn.transform_3d.html) instead
+    pub fn from_epsg(code: u32) -> Result<Crs, ConversionError> {
+        match code {
+            4326 => Ok(Crs::Wgs84),
+            4258 => Ok(Crs::Etrs89),
+            27700 => Ok(Crs::Osgb36),
+            3857 => Ok(Crs::WebMercator),
+            2157 => Ok(Crs::Itm),
+            29903 => Ok(Crs::IrishGrid),
+            25829 => Ok(Crs::Etrs89Utm(UtmZone::Zone29)),
+            25830 => Ok(Crs::Etrs89Utm(UtmZone::Zone30)),
+            25831 => Ok(Crs::Etrs89Utm(UtmZone::Zone31)),
+            32629 => Ok(Crs::Wgs84Utm(UtmZone::Zone29)),
+            32630 => Ok(Crs::Wgs84Utm(UtmZone::Zone30)),
+            32631 => Ok(Crs::Wgs84Utm(UtmZone::Zone31)),
+            _ => Err(ConversionError::UnsupportedEpsg { code: code }),
+        }
+    }
+
+    /// The EPSG code of the coordinate reference system, if it has one
+    pub fn epsg(&self) -> Option<u32> {
+        match *self {
+            Crs::Wgs84 => Some(4326),
+            Crs::Etrs89 => Some(4258),
+            Crs::Etrs89Grid => None,
+            Crs::Osgb36 => Some(27700),
+            Crs::WebMercator => Some(3857),
+            Crs::Itm => Some(2157),
+            Crs::IrishGrid => Some(29903),
+            Crs::Etrs89Utm(zone) => Some(25800 + zone.number() as u32),
+            Crs::Wgs84Utm(zone) => Some(32600 + zone.number() as u32),
+        }
+    }
+}
+
+/// A single conversion step between two coordinate reference systems
+#[derive(Clone, Copy)]
+enum Step {
+    /// A conversion function
+    Convert(fn(&f64, &f64) -> Result<(f64, f64), ConversionError>),
+    /// Projection of a longitude and latitude into a UTM zone
+    ToUtm(UtmZone),
+    /// Conversion of UTM coordinates in a zone to a longitude and latitude
+    FromUtm(UtmZone),
+}
+
+impl Step {
+    fn apply(&self, x: &f64, y: &f64) -> Result<(f64, f64), ConversionError> {
+        match *self {
+            Step::Convert(convert) => convert(x, y),
+            Step::ToUtm(zone) => convert_utm(x, y, Some(zone)),
+            Step::FromUtm(zone) => convert_utm_to_ll(x, y, zone),
+        }
+    }
+}
+
+fn identity(x: &f64, y: &f64) -> Result<(f64, f64), ConversionError> {
+    Ok((*x, *y))
+}
+
+// The conversions which make up the transformation graph, apart from UTM (see `edges`).
+// Where a function performs several steps in one (e.g. convert_osgb36), it's listed
+// as an edge in its own right, so that routing uses it in preference to the chain
+static EDGES: &'static [(Crs, Crs, Step)] = &[
+    (Crs::Wgs84, Crs::Etrs89, Step::Convert(identity)),
+    (Crs::Etrs89, Crs::Wgs84, Step::Convert(identity)),
+    (Crs::Etrs89, Crs::Etrs89Grid, Step::Convert(convert_etrs89)),
+    (Crs::Etrs89, Crs::Osgb36, Step::Convert(convert_osgb36)),
+    (Crs::Etrs89Grid, Crs::Etrs89, Step::Convert(convert_etrs89_to_ll)),
+    (Crs::Etrs89Grid, Crs::Osgb36, Step::Convert(convert_etrs89_to_osgb36)),
+    (Crs::Osgb36, Crs::Etrs89, Step::Convert(convert_osgb36_to_ll)),
+    (Crs::Osgb36, Crs::Etrs89Grid, Step::Convert(convert_osgb36_to_etrs89)),
+    (Crs::WebMercator, Crs::Wgs84, Step::Convert(convert_epsg3857_to_wgs84)),
+    (Crs::Wgs84, Crs::WebMercator, Step::Convert(convert_wgs84_to_epsg3857)),
+    (Crs::Etrs89, Crs::Itm, Step::Convert(convert_itm)),
+    (Crs::Itm, Crs::Etrs89, Step::Convert(convert_itm_to_ll)),
+    (Crs::Etrs89, Crs::IrishGrid, Step::Convert(convert_irish_grid)),
+    (Crs::IrishGrid, Crs::Etrs89, Step::Convert(convert_irish_grid_to_ll)),
+];
+
+static UTM_ZONES: &'static [UtmZone] = &[UtmZone::Zone29, UtmZone::Zone30, UtmZone::Zone31];
+
+// The systems which can be reached from a system in a single step.
+// Each UTM zone is reached from the longitude and latitude system on which it's based
+fn edges(crs: Crs) -> Vec<(Crs, Step)> {
+    let mut next: Vec<(Crs, Step)> = EDGES.iter()
+        .filter(|&&(start, _, _)| start == crs)
+        .map(|&(_, end, step)| (end, step))
+        .collect();
+    match crs {
+        Crs::Etrs89 => {
+            next.extend(UTM_ZONES.iter().map(|&zone| (Crs::Etrs89Utm(zone), Step::ToUtm(zone))))
+        }
+        Crs::Wgs84 => {
+            next.extend(UTM_ZONES.iter().map(|&zone| (Crs::Wgs84Utm(zone), Step::ToUtm(zone))))
+        }
+        Crs::Etrs89Utm(zone) => next.push((Crs::Etrs89, Step::FromUtm(zone))),
+        Crs::Wgs84Utm(zone) => next.push((Crs::Wgs84, Step::FromUtm(zone))),
+        _ => (),
+    }
+    next
+}
+
+// Find the shortest sequence of steps between two systems, using a breadth-first search
+fn route(from: Crs, to: Crs) -> Option<Vec<Step>> {
+    let mut queue = VecDeque::new();
+    queue.push_back((from, vec![]));
+    let mut visited = vec![from];
+    while let Some((crs, steps)) = queue.pop_front() {
+        if crs == to {
+            return Some(steps);
+        }
+        for (end, step) in edges(crs) {
+            if !visited.contains(&end) {
+                visited.push(end);
+                let mut next = steps.clone();
+                next.push(step);
+                queue.push_back((end, next));
+            }
+        }
+    }
+    None
+}
+
+/// Converts coordinates from one [`Crs`](enum.Crs.html) to another, by chaining together
+/// the crate's conversion functions. Every pair of systems is connected
+///
+/// The threaded and FFI functions which convert between two systems, such as
+/// `convert_to_osgb36_threaded_vec`, are built on a `Transformer`
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{Crs, Transformer};
+/// // Web Mercator → WGS84 → OSGB36
+/// let transformer = Transformer::new(Crs::WebMercator, Crs::Osgb36);
+/// let (easting, northing) = transformer.transform(&-36364.73, &6708665.97).unwrap();
+/// ```
+pub struct Transformer {
+    from: Crs,
+    to: Crs,
+    steps: Vec<Step>,
+}
+
+impl Transformer {
+    /// Create a transformer between two systems
+    pub fn new(from: Crs, to: Crs) -> Transformer {
+        Transformer {
+            from: from,
+            to: to,
+            steps: route(from, to).expect("the transformation graph is connected"),
+        }
+    }
+
+    /// Create a transformer between two EPSG codes, or return an error if either code is
+    /// unsupported
+    pub fn from_epsg(from_epsg: u32, to_epsg: u32) -> Result<Transformer, ConversionError> {
+        Ok(Transformer::new(Crs::from_epsg(from_epsg)?, Crs::from_epsg(to_epsg)?))
+    }
+
+    /// The source coordinate reference system
+    pub fn from_crs(&self) -> Crs {
+        self.from
+    }
+
+    /// The target coordinate reference system
+    pub fn to_crs(&self) -> Crs {
+        self.to
+    }
+
+    /// Transform a single coordinate pair
+    pub fn transform(&self, x: &f64, y: &f64) -> Result<(f64, f64), ConversionError> {
+        let mut res = (*x, *y);
+        for step in &self.steps {
+            res = step.apply(&res.0, &res.1)?;
+        }
+        Ok(res)
+    }
+
+    /// Transform slices of coordinates in place, using multiple threads.
+    /// Points which can't be transformed are set to `NAN`
+    pub fn transform_threaded_vec<'a>(&self,
+                                      xs: &'a mut [f64],
+                                      ys: &'a mut [f64])
+                                      -> (&'a mut [f64], &'a mut [f64]) {
+        convert_vec_direct(xs, ys, |x: &f64, y: &f64| self.transform(x, y))
+    }
+
+    /// As [`transform_threaded_vec`](#method.transform_threaded_vec), but also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    pub fn transform_threaded_vec_with_status<'a>(&self,
+                                                  xs: &'a mut [f64],
+                                                  ys: &'a mut [f64],
+                                                  status: &'a mut [u8])
+                                                  -> (&'a mut [f64], &'a mut [f64]) {
+        convert_vec_direct_status(xs, ys, status, |x: &f64, y: &f64| self.transform(x, y))
+    }
+}
+
+/// Convert slices of coordinates in place from one EPSG code to another, using multiple threads
+///
+/// Points which can't be converted are set to `NAN`. An error is returned if either code is
+/// unsupported.
+///
+/// # Examples
+///
+/// ```
+/// let xs: &mut [f64] = &mut [1.716073973];
+/// let ys: &mut [f64] = &mut [52.658007833];
+/// let (eastings, northings) = lonlat_bng::transform(4326, 27700, xs, ys).unwrap();
+/// ```
+pub fn transform<'a>(from_epsg: u32,
+                     to_epsg: u32,
+                     xs: &'a mut [f64],
+                     ys: &'a mut [f64])
+                     -> Result<(&'a mut [f64], &'a mut [f64]), ConversionError> {
+    let transformer = Transformer::from_epsg(from_epsg, to_epsg)?;
+    Ok(transformer.transform_threaded_vec(xs, ys))
+}
+
+type Convert3d = fn(&f64, &f64, &f64) -> Result<(f64, f64, f64), ConversionError>;
+
+fn identity_3d(x: &f64, y: &f64, h: &f64) -> Result<(f64, f64, f64), ConversionError> {
+    Ok((*x, *y, *h))
+}
+
+// The conversion between two EPSG codes which carry heights. ETRS89 and WGS84 are treated as
+// identical, as they are by Transformer
+fn conversion_3d(from_epsg: u32, to_epsg: u32) -> Result<Convert3d, ConversionError> {
+    for &code in &[from_epsg, to_epsg] {
+        if !SUPPORTED_EPSG_3D.contains(&code) {
+            return Err(ConversionError::UnsupportedEpsg { code: code });
+        }
+    }
+    match (from_epsg, to_epsg) {
+        (7405, 7405) => Ok(identity_3d),
+        (_, 7405) => Ok(convert_osgb36_3d),
+        (7405, _) => Ok(convert_osgb36_to_ll_3d),
+        _ => Ok(identity_3d),
+    }
+}
+
+/// Convert slices of coordinates and heights in place from one EPSG code to another, using
+/// multiple threads
+///
+/// The supported codes are 4326 and 4258, with ellipsoidal heights, and 7405 (British National
+/// Grid + ODN height). Conversions to and from 7405 use
+/// [`convert_osgb36_3d`](fn.convert_osgb36_3d.html) and
+/// [`convert_osgb36_to_etrs89_3d`](fn.convert_osgb36_to_etrs89_3d.html), so the heights are
+/// converted using OSGM15.
+///
+/// Points which can't be converted are set to `NAN`. An error is returned if either code is
+/// unsupported.
+///
+/// # Examples
+///
+/// ```
+/// let lons: &mut [f64] = &mut [1.716073973];
+/// let lats: &mut [f64] = &mut [52.658007833];
+/// let heights: &mut [f64] = &mut [108.050];
+/// let (eastings, northings, odn_heights) =
+///     lonlat_bng::transform_3d(4258, 7405, lons, lats, heights).unwrap();
+/// ```
+pub fn transform_3d<'a>(from_epsg: u32,
+                        to_epsg: u32,
+                        xs: &'a mut [f64],
+                        ys: &'a mut [f64],
+                        hs: &'a mut [f64])
+                        -> Result<(&'a mut [f64], &'a mut [f64], &'a mut [f64]), ConversionError> {
+    let convert = conversion_3d(from_epsg, to_epsg)?;
+    Ok(convert_vec_direct_3d(xs, ys, hs, convert))
+}
+
+/// Check whether a conversion from one EPSG code to another is supported by
+/// [`transform`](fn.transform.html). Use
+/// [`is_supported_epsg_pair_3d`](fn.is_supported_epsg_pair_3d.html) for codes with heights
+pub fn is_supported_epsg_pair(from_epsg: u32, to_epsg: u32) -> bool {
+    Crs::from_epsg(from_epsg).is_ok() && Crs::from_epsg(to_epsg).is_ok()
+}
+
+/// List every (from, to) pair of EPSG codes which can be converted by
+/// [`transform`](fn.transform.html). Use
+/// [`supported_epsg_pairs_3d`](fn.supported_epsg_pairs_3d.html) for codes with heights
+pub fn supported_epsg_pairs() -> Vec<(u32, u32)> {
+    let mut pairs = vec![];
+    for &from in SUPPORTED_EPSG.iter() {
+        for &to in SUPPORTED_EPSG.iter() {
+            if is_supported_epsg_pair(from, to) {
+                pairs.push((from, to));
+            }
+        }
+    }
+    pairs
+}
+
+/// Check whether a conversion from one EPSG code to another is supported by
+/// [`transform_3d`](fn.transform_3d.html)
+pub fn is_supported_epsg_pair_3d(from_epsg: u32, to_epsg: u32) -> bool {
+    conversion_3d(from_epsg, to_epsg).is_ok()
+}
+
+/// List every (from, to) pair of EPSG codes which can be converted by
+/// [`transform_3d`](fn.transform_3d.html)
+pub fn supported_epsg_pairs_3d() -> Vec<(u32, u32)> {
+    let mut pairs = vec![];
+    for &from in SUPPORTED_EPSG_3D.iter() {
+        for &to in SUPPORTED_EPSG_3D.iter() {
+            if is_supported_epsg_pair_3d(from, to) {
+                pairs.push((from, to));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use conversions::{convert_etrs89, convert_osgb36_to_epsg3857};
+
+    #[test]
+    fn test_route_prefers_direct_conversion() {
+        let transformer = Transformer::new(Crs::Etrs89, Crs::Etrs89Grid);
+        assert_eq!(1, transformer.steps.len());
+        assert_eq!(convert_etrs89(&1.716073973, &52.658007833),
+                   transformer.transform(&1.716073973, &52.658007833));
+    }
+
+    #[test]
+    fn test_graph_is_connected() {
+        let mut systems = vec![Crs::Wgs84,
+                               Crs::Etrs89,
+                               Crs::Etrs89Grid,
+                               Crs::Osgb36,
+                               Crs::WebMercator,
+                               Crs::Itm,
+                               Crs::IrishGrid];
+        for &zone in UTM_ZONES.iter() {
+            systems.push(Crs::Etrs89Utm(zone));
+            systems.push(Crs::Wgs84Utm(zone));
+        }
+        for &from in &systems {
+            for &to in &systems {
+                assert!(route(from, to).is_some(), "no route from {} to {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_crs() {
+        let transformer = Transformer::new(Crs::Osgb36, Crs::Osgb36);
+        assert_eq!((651409.804, 313177.450),
+                   transformer.transform(&651409.804, &313177.450).unwrap());
+    }
+
+    #[test]
+    fn test_chained_transformation() {
+        // Web Mercator → WGS84 → ETRS89 → ETRS89 grid
+        let transformer = Transformer::new(Crs::WebMercator, Crs::Etrs89Grid);
+        assert_eq!(3, transformer.steps.len());
+        let (lon, lat) = convert_epsg3857_to_wgs84(&-626172.1357121646, &6887893.4928337997)
+            .unwrap();
+        assert_eq!(convert_etrs89(&lon, &lat),
+                   transformer.transform(&-626172.1357121646, &6887893.4928337997));
+    }
+
+    #[test]
+    fn test_to_web_mercator() {
+        // OSGB36 → ETRS89 → WGS84 → Web Mercator
+        let transformer = Transformer::new(Crs::Osgb36, Crs::WebMercator);
+        assert_eq!(3, transformer.steps.len());
+        assert_eq!(convert_osgb36_to_epsg3857(&651409.792, &313177.448),
+                   transformer.transform(&651409.792, &313177.448));
+    }
+
+    #[test]
+    fn test_epsg_lookup() {
+        assert_eq!(Ok(Crs::Osgb36), Crs::from_epsg(27700));
+        // Converting BNG + ODN height as BNG would silently drop the heights
+        assert_eq!(Err(ConversionError::UnsupportedEpsg { code: 7405 }),
+                   Crs::from_epsg(7405));
+        assert_eq!(Some(4258), Crs::Etrs89.epsg());
+        assert_eq!(Ok(Crs::Itm), Crs::from_epsg(2157));
+        assert_eq!(Err(ConversionError::UnsupportedEpsg { code: 2193 }),
+                   Crs::from_epsg(2193));
+    }
+
+    #[test]
+    fn test_supported_epsg_pairs() {
+        let pairs = supported_epsg_pairs();
+        assert!(pairs.contains(&(3857, 27700)));
+        assert!(pairs.contains(&(4326, 4258)));
+        assert!(pairs.contains(&(27700, 3857)));
+        assert!(is_supported_epsg_pair(4258, 27700));
+        assert!(!is_supported_epsg_pair(4258, 7405));
+        assert!(is_supported_epsg_pair(27700, 2157));
+        assert!(!is_supported_epsg_pair(4326, 2193));
+    }
+
+    #[test]
+    fn test_utm_epsg() {
+        assert_eq!(Ok(Crs::Etrs89Utm(UtmZone::Zone30)), Crs::from_epsg(25830));
+        assert_eq!(Some(32631), Crs::Wgs84Utm(UtmZone::Zone31).epsg());
+        assert_eq!("WGS84 / UTM zone 30N", Crs::Wgs84Utm(UtmZone::Zone30).to_string());
+        // ETRS89 / UTM 30N → ETRS89 → ETRS89 / UTM 31N
+        let transformer = Transformer::from_epsg(25830, 25831).unwrap();
+        assert_eq!(2, transformer.steps.len());
+        let (lon, lat) = convert_utm_to_ll(&700000., &5800000., UtmZone::Zone30).unwrap();
+        assert_eq!(convert_utm(&lon, &lat, Some(UtmZone::Zone31)),
+                   transformer.transform(&700000., &5800000.));
+    }
+
+    #[test]
+    fn test_epsg_transform() {
+        let xs: &mut [f64] = &mut [-626172.1357121646];
+        let ys: &mut [f64] = &mut [6887893.4928337997];
+        let (lons, lats) = transform(3857, 4326, xs, ys).unwrap();
+        assert_eq!((-5.625000000783013, 52.48278022732355), (lons[0], lats[0]));
+    }
+
+    #[test]
+    fn test_epsg_transform_3d() {
+        let lons: &mut [f64] = &mut [1.716073973];
+        let lats: &mut [f64] = &mut [52.658007833];
+        let hs: &mut [f64] = &mut [108.050];
+        {
+            let (es, ns, hs) = transform_3d(4258, 7405, lons, lats, hs).unwrap();
+            assert_eq!((651409.804, 313177.450, 63.822), (es[0], ns[0], hs[0]));
+        }
+        let expected = convert_osgb36_to_ll_3d(&lons[0], &lats[0], &hs[0]).unwrap();
+        let (lons, lats, hs) = transform_3d(7405, 4326, lons, lats, hs).unwrap();
+        assert_eq!(expected, (lons[0], lats[0], hs[0]));
+    }
+
+    #[test]
+    fn test_unsupported_epsg_transform_3d() {
+        let xs: &mut [f64] = &mut [516276.];
+        let ys: &mut [f64] = &mut [173141.];
+        let hs: &mut [f64] = &mut [10.];
+        // 27700 has no heights, and 7405 has no 2D equivalent
+        assert_eq!(ConversionError::UnsupportedEpsg { code: 27700 },
+                   transform_3d(4258, 27700, xs, ys, hs).unwrap_err());
+        assert_eq!(ConversionError::UnsupportedEpsg { code: 7405 },
+                   transform(7405, 4258, xs, ys).unwrap_err());
+        let pairs = supported_epsg_pairs_3d();
+        assert_eq!(9, pairs.len());
+        assert!(pairs.contains(&(4326, 7405)));
+        assert!(pairs.contains(&(7405, 4258)));
+        assert!(is_supported_epsg_pair_3d(4258, 4326));
+        assert!(!is_supported_epsg_pair_3d(27700, 7405));
+    }
+
+    #[test]
+    fn test_unsupported_epsg_transform() {
+        let xs: &mut [f64] = &mut [0.];
+        let ys: &mut [f64] = &mut [0.];
+        assert_eq!(ConversionError::UnsupportedEpsg { code: 9999 },
+                   transform(4326, 9999, xs, ys).unwrap_err());
+    }
+
+    #[test]
+    fn test_threaded_transformation() {
+        let transformer = Transformer::new(Crs::Wgs84, Crs::Etrs89Grid);
+        let lons: &mut [f64] = &mut [1.716073973, 181.];
+        let lats: &mut [f64] = &mut [52.658007833, 52.658007833];
+        let status: &mut [u8] = &mut [255; 2];
+        {
+            let (eastings, northings) =
+                transformer.transform_threaded_vec_with_status(lons, lats, status);
+            assert_eq!(651307.003, eastings[0]);
+            assert_eq!(313255.686, northings[0]);
+            assert!(eastings[1].is_nan());
+        }
+        assert_eq!(&[0, 1], status);
+    }
+}
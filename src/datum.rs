@@ -0,0 +1,175 @@
+//! This module provides OSGM15 vertical datum flags
+//!
+//! OSGM15 geoid heights refer to several local vertical datums, and the official
+//! [OSTN15 / OSGM15 data file](https://www.ordnancesurvey.co.uk/business-and-government/help-and-support/navigation-technology/os-net/formats-for-developers.html)
+//! carries a datum flag for each grid node. The OSTN15 lookup table used for the horizontal
+//! and height shifts doesn't include these flags, so they're loaded from the data file using
+//! [`DatumFlags`](struct.DatumFlags.html).
+use std::io;
+use std::io::BufRead;
+
+use errors::ConversionError;
+
+// The OSTN15 grid is 701 nodes wide and 1251 nodes high
+const GRID_WIDTH: usize = 701;
+const GRID_HEIGHT: usize = 1251;
+
+/// The local vertical datum to which an OSGM15 height refers
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerticalDatum {
+    OutsideModel = 0,
+    /// Ordnance Datum Newlyn
+    Newlyn = 1,
+    StMarys = 2,
+    Douglas02 = 3,
+    Stornoway = 4,
+    StKilda = 5,
+    Lerwick = 6,
+    NewlynOrkney = 7,
+    FairIsle = 8,
+    FlannanIsles = 9,
+    NorthRona = 10,
+    SuleSkerry = 11,
+    Foula = 12,
+    MalinHead = 13,
+    Belfast = 14,
+    Offshore = 15,
+}
+
+impl VerticalDatum {
+    /// Look up a datum from its OSGM15 flag value
+    pub fn from_flag(flag: u8) -> Option<VerticalDatum> {
+        match flag {
+            0 => Some(VerticalDatum::OutsideModel),
+            1 => Some(VerticalDatum::Newlyn),
+            2 => Some(VerticalDatum::StMarys),
+            3 => Some(VerticalDatum::Douglas02),
+            4 => Some(VerticalDatum::Stornoway),
+            5 => Some(VerticalDatum::StKilda),
+            6 => Some(VerticalDatum::Lerwick),
+            7 => Some(VerticalDatum::NewlynOrkney),
+            8 => Some(VerticalDatum::FairIsle),
+            9 => Some(VerticalDatum::FlannanIsles),
+            10 => Some(VerticalDatum::NorthRona),
+            11 => Some(VerticalDatum::SuleSkerry),
+            12 => Some(VerticalDatum::Foula),
+            13 => Some(VerticalDatum::MalinHead),
+            14 => Some(VerticalDatum::Belfast),
+            15 => Some(VerticalDatum::Offshore),
+            _ => None,
+        }
+    }
+}
+
+/// OSGM15 vertical datum flags for each node of the OSTN15 grid
+pub struct DatumFlags {
+    // indexed by OSTN15 key - 1; None for nodes missing from the data file
+    flags: Vec<Option<VerticalDatum>>,
+}
+
+impl DatumFlags {
+    /// Read datum flags from the official `OSTN15_OSGM15_DataFile.txt`
+    ///
+    /// Each data line has the form
+    /// `Point_ID,ETRS89_Easting,ETRS89_Northing,ETRS89_OSGB36_EShift,ETRS89_OSGB36_NShift,ETRS89_ODN_HeightShift,Height_Datum_Flag`.
+    /// The header line is skipped.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<DatumFlags> {
+        let mut flags = vec![None; GRID_WIDTH * GRID_HEIGHT];
+        for line in reader.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.trim().split(',').collect();
+            let key = match fields[0].trim().parse::<usize>() {
+                Ok(key) => key,
+                // header or blank line
+                Err(_) => continue,
+            };
+            let flag = fields.last()
+                .and_then(|flag| flag.trim().parse::<u8>().ok())
+                .and_then(VerticalDatum::from_flag);
+            match flag {
+                Some(flag) if key >= 1 && key <= flags.len() => flags[key - 1] = Some(flag),
+                _ => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                              format!("invalid datum flag line: {}", line)))
+                }
+            }
+        }
+        Ok(DatumFlags { flags: flags })
+    }
+
+    /// Look up the datum flag of a grid node, using the same indices as
+    /// [`get_ostn_ref`](utils/fn.get_ostn_ref.html)
+    ///
+    /// Nodes outside the grid are `OutsideOstn15`; nodes inside it which the data file didn't
+    /// cover are `MissingDatumFlag`
+    pub fn get(&self, x: &i32, y: &i32) -> Result<VerticalDatum, ConversionError> {
+        if *x < 0 || *y < 0 || *x as usize >= GRID_WIDTH || *y as usize >= GRID_HEIGHT {
+            return Err(ConversionError::OutsideOstn15 {
+                easting_index: *x,
+                northing_index: *y,
+            });
+        }
+        let flag = self.flags[*x as usize + *y as usize * GRID_WIDTH];
+        flag.ok_or(ConversionError::MissingDatumFlag {
+            easting_index: *x,
+            northing_index: *y,
+        })
+    }
+
+    /// The datum which governs an ETRS89 grid position: that of the nearest grid node
+    pub fn governing(&self, x: &f64, y: &f64) -> Result<VerticalDatum, ConversionError> {
+        let (e_index, n_index) = nearest_node(x, y);
+        self.get(&e_index, &n_index)
+    }
+}
+
+// The grid node nearest to an ETRS89 grid position
+fn nearest_node(x: &f64, y: &f64) -> (i32, i32) {
+    ((*x / 1000.).round() as i32, (*y / 1000.).round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DATA: &'static str = "\
+Point_ID,ETRS89_Easting,ETRS89_Northing,ETRS89_OSGB36_EShift,ETRS89_OSGB36_NShift,ETRS89_ODN_HeightShift,Height_Datum_Flag
+1483,80000,2000,82.600,-51.990,57.150,2
+1484,81000,2000,82.640,-51.980,57.180,2
+2184,80000,3000,82.610,-51.950,57.160,1
+2185,81000,3000,82.650,-51.940,57.200,2
+";
+
+    #[test]
+    fn test_read_flags() {
+        let flags = DatumFlags::from_reader(Cursor::new(DATA)).unwrap();
+        assert_eq!(Ok(VerticalDatum::StMarys), flags.get(&80, &2));
+        assert_eq!(Ok(VerticalDatum::Newlyn), flags.get(&80, &3));
+        assert_eq!(Err(ConversionError::MissingDatumFlag {
+                       easting_index: 82,
+                       northing_index: 2,
+                   }),
+                   flags.get(&82, &2));
+        assert_eq!(Err(ConversionError::OutsideOstn15 {
+                       easting_index: 701,
+                       northing_index: 2,
+                   }),
+                   flags.get(&701, &2));
+    }
+
+    #[test]
+    fn test_governing_flag_is_nearest_node() {
+        let flags = DatumFlags::from_reader(Cursor::new(DATA)).unwrap();
+        assert_eq!(Ok(VerticalDatum::StMarys), flags.governing(&80400., &2400.));
+        assert_eq!(Ok(VerticalDatum::Newlyn), flags.governing(&80400., &2600.));
+        assert_eq!(Ok(VerticalDatum::StMarys), flags.governing(&80600., &2600.));
+    }
+
+    #[test]
+    fn test_bad_flag_line() {
+        let data = "1483,80000,2000,82.600,-51.990,57.150,99\n";
+        assert!(DatumFlags::from_reader(Cursor::new(data)).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! This module provides conversions between geodetic (longitude, latitude, ellipsoidal height)
+//! and Earth-centred, Earth-fixed (ECEF) cartesian coordinates
+use std::f64;
+use std::mem;
+
+use conversions::convert_osgb36_3d;
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use utils::check;
+use super::convert_vec_direct_3d;
+
+// Stop iterating for latitude once successive values differ by less than this (radians)
+const LAT_EPSILON: f64 = 1e-12;
+const MAX_ITERATIONS: usize = 100;
+
+/// Convert longitude, latitude (decimal degrees) and ellipsoidal height (m) to ECEF X, Y, Z (m)
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{geodetic_to_ecef, Ellipsoid};
+/// let (x, y, z) = geodetic_to_ecef(&1.717921583, &52.657570306, &24.7, &Ellipsoid::AIRY_1830).unwrap();
+/// ```
+pub fn geodetic_to_ecef(longitude: &f64,
+                        latitude: &f64,
+                        height: &f64,
+                        ellipsoid: &Ellipsoid)
+                        -> Result<(f64, f64, f64), ConversionError> {
+    let lon = check(*longitude, (-180., 180.), Axis::Longitude)?.to_radians();
+    let lat = check(*latitude, (-90., 90.), Axis::Latitude)?.to_radians();
+    let h = check(*height, (f64::MIN, f64::MAX), Axis::Height)?;
+    let e2 = ellipsoid.e2();
+    // Transverse radius of curvature
+    let nu = ellipsoid.semi_major() / (1. - e2 * lat.sin().powi(2)).sqrt();
+    let x = (nu + h) * lat.cos() * lon.cos();
+    let y = (nu + h) * lat.cos() * lon.sin();
+    let z = ((1. - e2) * nu + h) * lat.sin();
+    Ok((x, y, z))
+}
+
+/// Convert ECEF X, Y, Z (m) to longitude, latitude (decimal degrees) and ellipsoidal height (m)
+///
+/// Latitude is obtained by an iterative procedure
+pub fn ecef_to_geodetic(x: &f64,
+                        y: &f64,
+                        z: &f64,
+                        ellipsoid: &Ellipsoid)
+                        -> Result<(f64, f64, f64), ConversionError> {
+    for &(axis, value) in &[(Axis::X, *x), (Axis::Y, *y), (Axis::Z, *z)] {
+        check(value, (f64::MIN, f64::MAX), axis)?;
+    }
+    let a = ellipsoid.semi_major();
+    let e2 = ellipsoid.e2();
+    let p = (x.powi(2) + y.powi(2)).sqrt();
+    // Initial value
+    let mut lat = z.atan2(p * (1. - e2));
+    let mut latold = 2. * f64::consts::PI;
+    let mut nu = a / (1. - e2 * lat.sin().powi(2)).sqrt();
+    let mut iterations = 0;
+    while (lat - latold).abs() > LAT_EPSILON {
+        iterations += 1;
+        if iterations > MAX_ITERATIONS {
+            return Err(ConversionError::NoConvergence);
+        }
+        mem::swap(&mut lat, &mut latold);
+        nu = a / (1. - e2 * latold.sin().powi(2)).sqrt();
+        lat = (z + e2 * nu * latold.sin()).atan2(p);
+    }
+    let lon = y.atan2(*x);
+    // Avoid dividing by cos(lat) close to the poles
+    let h = if lat.abs() < f64::consts::FRAC_PI_4 {
+        p / lat.cos() - nu
+    } else {
+        z / lat.sin() - (1. - e2) * nu
+    };
+    Ok((lon.to_degrees(), lat.to_degrees(), h))
+}
+
+/// Convert ETRS89 ECEF X, Y, Z (m) to OSGB36 Eastings, Northings and ODN height,
+/// using OSTN15 / OSGM15 data
+pub fn convert_ecef_to_osgb36(x: &f64,
+                              y: &f64,
+                              z: &f64)
+                              -> Result<(f64, f64, f64), ConversionError> {
+    let (lon, lat, h) = ecef_to_geodetic(x, y, z, &Ellipsoid::GRS80)?;
+    convert_osgb36_3d(&lon, &lat, &h)
+}
+
+/// A threaded wrapper for [`lonlat_bng::geodetic_to_ecef`](fn.geodetic_to_ecef.html)
+pub fn geodetic_to_ecef_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                         latitudes: &'a mut [f64],
+                                         heights: &'a mut [f64],
+                                         ellipsoid: &Ellipsoid)
+                                         -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(longitudes,
+                          latitudes,
+                          heights,
+                          |lon: &f64, lat: &f64, h: &f64| geodetic_to_ecef(lon, lat, h, ellipsoid))
+}
+
+/// A threaded wrapper for [`lonlat_bng::ecef_to_geodetic`](fn.ecef_to_geodetic.html)
+pub fn ecef_to_geodetic_threaded_vec<'a>(xs: &'a mut [f64],
+                                         ys: &'a mut [f64],
+                                         zs: &'a mut [f64],
+                                         ellipsoid: &Ellipsoid)
+                                         -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(xs,
+                          ys,
+                          zs,
+                          |x: &f64, y: &f64, z: &f64| ecef_to_geodetic(x, y, z, ellipsoid))
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_ecef_to_osgb36`](fn.convert_ecef_to_osgb36.html)
+pub fn convert_ecef_to_osgb36_threaded_vec<'a>
+    (xs: &'a mut [f64],
+     ys: &'a mut [f64],
+     zs: &'a mut [f64])
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(xs, ys, zs, convert_ecef_to_osgb36)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geodetic_to_ecef() {
+        // Example from Annexe B of "A guide to coordinate systems in Great Britain"
+        // 52°39′27.2531″N, 1°43′4.5177″E, 24.7 m on the Airy 1830 ellipsoid
+        let lat = 52. + 39. / 60. + 27.2531 / 3600.;
+        let lon = 1. + 43. / 60. + 4.5177 / 3600.;
+        let (x, y, z) = geodetic_to_ecef(&lon, &lat, &24.7, &Ellipsoid::AIRY_1830).unwrap();
+        assert!((x - 3874938.849).abs() < 0.001);
+        assert!((y - 116218.624).abs() < 0.001);
+        assert!((z - 5047168.208).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_ecef_to_geodetic() {
+        // Example from Annexe B of "A guide to coordinate systems in Great Britain"
+        // expected: 53°36′43.1653″N, 1°39′51.9920″W, 299.800 m on the GRS80 ellipsoid
+        let (lon, lat, h) =
+            ecef_to_geodetic(&3790644.900, &-110149.210, &5111482.970, &Ellipsoid::GRS80).unwrap();
+        assert!((lat - (53. + 36. / 60. + 43.1653 / 3600.)).abs() < 0.00000001);
+        assert!((lon - -(1. + 39. / 60. + 51.9920 / 3600.)).abs() < 0.00000001);
+        assert!((h - 299.800).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_round_trip_airy() {
+        let (x, y, z) = geodetic_to_ecef(&-3.5, &58.2, &100., &Ellipsoid::AIRY_1830).unwrap();
+        let (lon, lat, h) = ecef_to_geodetic(&x, &y, &z, &Ellipsoid::AIRY_1830).unwrap();
+        assert!((lon - -3.5).abs() < 0.000000001);
+        assert!((lat - 58.2).abs() < 0.000000001);
+        assert!((h - 100.).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_threaded_ecef() {
+        let lons: &mut [f64] = &mut [-3.5, 181.];
+        let lats: &mut [f64] = &mut [58.2, 58.2];
+        let heights: &mut [f64] = &mut [100., 100.];
+        let (xs, ys, zs) = geodetic_to_ecef_threaded_vec(lons, lats, heights, &Ellipsoid::GRS80);
+        let expected = geodetic_to_ecef(&-3.5, &58.2, &100., &Ellipsoid::GRS80).unwrap();
+        assert_eq!(expected, (xs[0], ys[0], zs[0]));
+        assert!(xs[1].is_nan());
+    }
+}
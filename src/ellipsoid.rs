@@ -0,0 +1,156 @@
+//! This module provides the reference ellipsoids used by the conversions
+//!
+//! Each ellipsoid stores its eccentricities, flattening, third flattening and meridian arc
+//! coefficients alongside its axes, so that they aren't recomputed for every conversion.
+
+// Build an ellipsoid from its semi-major and semi-minor axes. The derived parameters are
+// constant expressions, so this can be used for associated consts
+macro_rules! ellipsoid {
+    ($a:expr, $b:expr) => {
+        ellipsoid!(@derived $a, $b, ($a - $b) / ($a + $b))
+    };
+    (@derived $a:expr, $b:expr, $n:expr) => {
+        Ellipsoid {
+            semi_major: $a,
+            semi_minor: $b,
+            flattening: ($a - $b) / $a,
+            e2: ($a * $a - $b * $b) / ($a * $a),
+            ep2: ($a * $a - $b * $b) / ($b * $b),
+            n: $n,
+            meridian_arc: [1. + $n + 5. / 4. * $n * $n + 5. / 4. * $n * $n * $n,
+                           3. * $n + 3. * $n * $n + 21. / 8. * $n * $n * $n,
+                           15. / 8. * $n * $n + 15. / 8. * $n * $n * $n,
+                           35. / 24. * $n * $n * $n],
+        }
+    };
+}
+
+/// A reference ellipsoid, defined by its semi-major and semi-minor axes (m)
+///
+/// Use [`new`](#method.new) or [`from_flattening`](#method.from_flattening) to define an
+/// ellipsoid which isn't provided here
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    semi_major: f64,
+    semi_minor: f64,
+    flattening: f64,
+    e2: f64,
+    ep2: f64,
+    n: f64,
+    meridian_arc: [f64; 4],
+}
+
+impl Ellipsoid {
+    /// The Airy 1830 ellipsoid, used by OSGB36
+    pub const AIRY_1830: Ellipsoid = ellipsoid!(6377563.396, 6356256.909);
+
+    /// The Airy Modified ellipsoid, used by the Irish Grid (TM65 / TM75)
+    pub const AIRY_MODIFIED: Ellipsoid = ellipsoid!(6377340.189, 6356034.447);
+
+    /// The GRS80 ellipsoid, used by ETRS89 (and, to within a millimetre, WGS84)
+    pub const GRS80: Ellipsoid = ellipsoid!(6378137.000, 6356752.3141);
+
+    /// The WGS84 ellipsoid
+    pub const WGS84: Ellipsoid = ellipsoid!(6378137.000, 6378137.000 * (1. - 1. / 298.257223563));
+
+    /// The International 1924 (Hayford) ellipsoid, used by ED50
+    pub const INTERNATIONAL_1924: Ellipsoid = ellipsoid!(6378388.000,
+                                                         6378388.000 * (1. - 1. / 297.));
+
+    /// The Bessel 1841 ellipsoid, used by DHDN and other central European datums
+    pub const BESSEL_1841: Ellipsoid = ellipsoid!(6377397.155,
+                                                  6377397.155 * (1. - 1. / 299.1528128));
+
+    /// The Clarke 1866 ellipsoid, used by NAD27
+    pub const CLARKE_1866: Ellipsoid = ellipsoid!(6378206.400, 6356583.800);
+
+    pub fn new(semi_major: f64, semi_minor: f64) -> Ellipsoid {
+        ellipsoid!(semi_major, semi_minor)
+    }
+
+    /// An ellipsoid defined by its semi-major axis (m) and inverse flattening
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::Ellipsoid;
+    /// assert_eq!(Ellipsoid::WGS84, Ellipsoid::from_flattening(6378137., 298.257223563));
+    /// ```
+    pub fn from_flattening(semi_major: f64, inverse_flattening: f64) -> Ellipsoid {
+        Ellipsoid::new(semi_major, semi_major * (1. - 1. / inverse_flattening))
+    }
+
+    /// The semi-major axis, a (m)
+    pub fn semi_major(&self) -> f64 {
+        self.semi_major
+    }
+
+    /// The semi-minor axis, b (m)
+    pub fn semi_minor(&self) -> f64 {
+        self.semi_minor
+    }
+
+    /// The flattening, (a - b) / a
+    pub fn flattening(&self) -> f64 {
+        self.flattening
+    }
+
+    /// The squared eccentricity
+    pub fn e2(&self) -> f64 {
+        self.e2
+    }
+
+    /// The squared second eccentricity, (a² - b²) / b²
+    pub fn ep2(&self) -> f64 {
+        self.ep2
+    }
+
+    /// The third flattening, (a - b) / (a + b)
+    pub fn n(&self) -> f64 {
+        self.n
+    }
+
+    /// The coefficients of the terms of the OS meridional arc series, in the third
+    /// flattening `n`
+    ///
+    /// The arc from latitude φ0 to φ is
+    /// `b (c0 (φ − φ0) − c1 sin(φ − φ0) cos(φ + φ0) + c2 sin 2(φ − φ0) cos 2(φ + φ0)
+    /// − c3 sin 3(φ − φ0) cos 3(φ + φ0))`
+    pub fn meridian_arc_coefficients(&self) -> [f64; 4] {
+        self.meridian_arc
+    }
+
+    /// The Gaussian mean radius of curvature, √(ρν), at a latitude (decimal degrees)
+    pub fn mean_radius(&self, latitude: &f64) -> f64 {
+        let sp2 = latitude.to_radians().sin().powi(2);
+        self.semi_major * (1. - self.e2).sqrt() / (1. - self.e2 * sp2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derived_parameters() {
+        let airy = Ellipsoid::AIRY_1830;
+        assert_eq!(airy, Ellipsoid::new(6377563.396, 6356256.909));
+        assert!((airy.e2() - 0.00667054007).abs() < 1e-11);
+        assert!((airy.n() - 0.00167322033).abs() < 1e-11);
+        assert!((1. / Ellipsoid::INTERNATIONAL_1924.flattening() - 297.).abs() < 1e-9);
+        let grs80 = Ellipsoid::GRS80;
+        assert!((grs80.ep2() - grs80.e2() / (1. - grs80.e2())).abs() < 1e-15);
+        // The semi-minor axes of GRS80 and WGS84 differ by 0.1 mm
+        assert!((grs80.semi_minor() - Ellipsoid::WGS84.semi_minor()).abs() < 0.0002);
+    }
+
+    #[test]
+    fn test_meridian_arc_coefficients() {
+        let n = Ellipsoid::CLARKE_1866.n();
+        let c = Ellipsoid::CLARKE_1866.meridian_arc_coefficients();
+        assert!((c[0] - (1. + n * (1. + 5. / 4. * n * (1. + n)))).abs() < 1e-15);
+        assert!((c[1] - 3. * n * (1. + n * (1. + 7. / 8. * n))).abs() < 1e-15);
+        assert!((c[2] - 15. / 8. * n * (n * (1. + n))).abs() < 1e-15);
+        assert!((c[3] - 35. / 24. * n.powi(3)).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,223 @@
+//! This module provides the error type returned by the conversion functions
+use std::error::Error;
+use std::fmt;
+
+/// The input axis to which a [`ConversionError`](enum.ConversionError.html) refers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    Longitude,
+    Latitude,
+    Easting,
+    Northing,
+    Height,
+    /// A cartesian or projected x coordinate
+    X,
+    /// A cartesian or projected y coordinate
+    Y,
+    /// A cartesian z coordinate
+    Z,
+    /// A bearing or azimuth
+    Bearing,
+    /// A distance along a line
+    Distance,
+}
+
+impl fmt::Display for Axis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Axis::Longitude => "longitude",
+            Axis::Latitude => "latitude",
+            Axis::Easting => "easting",
+            Axis::Northing => "northing",
+            Axis::Height => "height",
+            Axis::X => "x",
+            Axis::Y => "y",
+            Axis::Z => "z",
+            Axis::Bearing => "bearing",
+            Axis::Distance => "distance",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The reason a conversion failed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConversionError {
+    /// An input value lies outside the range permitted for its axis
+    OutOfBounds {
+        axis: Axis,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+    /// An input value is `NaN` or infinite
+    NonFinite { axis: Axis, value: f64 },
+    /// The point falls into a cell which has no OSTN15 shift data.
+    /// The indices are those of the missing 1 km grid node
+    OutsideOstn15 { easting_index: i32, northing_index: i32 },
+    /// The iterative OSTN15 inverse transformation failed to converge
+    NoConvergence,
+    /// The EPSG code doesn't correspond to a supported coordinate reference system
+    UnsupportedEpsg { code: u32 },
+    /// An alphanumeric grid reference couldn't be parsed, or can't be written at the requested precision
+    InvalidGridReference,
+    /// The tile indices lie outside the tile grid at that zoom level
+    InvalidTile { zoom: u32, x: u32, y: u32 },
+    /// The OSTN15 grid node which governs the point's height has no OSGM15 datum flag in the
+    /// loaded [`DatumFlags`](struct.DatumFlags.html)
+    MissingDatumFlag { easting_index: i32, northing_index: i32 },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConversionError::OutOfBounds { axis, value, min, max } => {
+                write!(f,
+                       "{} {} is outside the permitted range ({} to {})",
+                       axis,
+                       value,
+                       min,
+                       max)
+            }
+            ConversionError::NonFinite { axis, value } => {
+                write!(f, "{} {} is not a finite number", axis, value)
+            }
+            ConversionError::OutsideOstn15 { easting_index, northing_index } => {
+                write!(f,
+                       "no OSTN15 data for grid node ({}, {})",
+                       easting_index,
+                       northing_index)
+            }
+            ConversionError::NoConvergence => {
+                write!(f, "the OSTN15 inverse transformation did not converge")
+            }
+            ConversionError::UnsupportedEpsg { code } => {
+                write!(f, "EPSG:{} is not a supported coordinate reference system", code)
+            }
+            ConversionError::InvalidGridReference => write!(f, "invalid grid reference"),
+            ConversionError::InvalidTile { zoom, x, y } => {
+                write!(f, "tile {}/{}/{} does not exist", zoom, x, y)
+            }
+            ConversionError::MissingDatumFlag { easting_index, northing_index } => {
+                write!(f,
+                       "no OSGM15 datum flag for grid node ({}, {})",
+                       easting_index,
+                       northing_index)
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {
+    fn description(&self) -> &str {
+        match *self {
+            ConversionError::OutOfBounds { .. } => "input coordinate out of bounds",
+            ConversionError::NonFinite { .. } => "input coordinate is not finite",
+            ConversionError::OutsideOstn15 { .. } => "point is outside OSTN15 coverage",
+            ConversionError::NoConvergence => "OSTN15 inverse transformation did not converge",
+            ConversionError::UnsupportedEpsg { .. } => "unsupported EPSG code",
+            ConversionError::InvalidGridReference => "invalid grid reference",
+            ConversionError::InvalidTile { .. } => "invalid tile",
+            ConversionError::MissingDatumFlag { .. } => "missing OSGM15 datum flag",
+        }
+    }
+}
+
+/// Per-point status codes, as written by the `*_with_status` batch conversion functions
+///
+/// The codes are stable, and are written as `u8` values so that they can be passed across the FFI boundary
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    Ok = 0,
+    LongitudeOutOfBounds = 1,
+    LatitudeOutOfBounds = 2,
+    EastingOutOfBounds = 3,
+    NorthingOutOfBounds = 4,
+    NonFinite = 5,
+    OutsideOstn15 = 6,
+    NoConvergence = 7,
+    UnsupportedEpsg = 9,
+    HeightOutOfBounds = 10,
+    XOutOfBounds = 11,
+    YOutOfBounds = 12,
+    ZOutOfBounds = 13,
+    InvalidGridReference = 14,
+    InvalidTile = 15,
+    BearingOutOfBounds = 16,
+    DistanceOutOfBounds = 17,
+    MissingDatumFlag = 18,
+}
+
+impl From<ConversionError> for Status {
+    fn from(err: ConversionError) -> Self {
+        match err {
+            ConversionError::OutOfBounds { axis, .. } => {
+                match axis {
+                    Axis::Longitude => Status::LongitudeOutOfBounds,
+                    Axis::Latitude => Status::LatitudeOutOfBounds,
+                    Axis::Easting => Status::EastingOutOfBounds,
+                    Axis::Northing => Status::NorthingOutOfBounds,
+                    Axis::Height => Status::HeightOutOfBounds,
+                    Axis::X => Status::XOutOfBounds,
+                    Axis::Y => Status::YOutOfBounds,
+                    Axis::Z => Status::ZOutOfBounds,
+                    Axis::Bearing => Status::BearingOutOfBounds,
+                    Axis::Distance => Status::DistanceOutOfBounds,
+                }
+            }
+            ConversionError::NonFinite { .. } => Status::NonFinite,
+            ConversionError::OutsideOstn15 { .. } => Status::OutsideOstn15,
+            ConversionError::NoConvergence => Status::NoConvergence,
+            ConversionError::UnsupportedEpsg { .. } => Status::UnsupportedEpsg,
+            ConversionError::InvalidGridReference => Status::InvalidGridReference,
+            ConversionError::InvalidTile { .. } => Status::InvalidTile,
+            ConversionError::MissingDatumFlag { .. } => Status::MissingDatumFlag,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_out_of_bounds() {
+        let err = ConversionError::OutOfBounds {
+            axis: Axis::Longitude,
+            value: -8.5,
+            min: -7.56,
+            max: 1.78,
+        };
+        assert_eq!("longitude -8.5 is outside the permitted range (-7.56 to 1.78)",
+                   err.to_string());
+    }
+
+    #[test]
+    fn test_display_outside_ostn15() {
+        let err = ConversionError::OutsideOstn15 {
+            easting_index: 999,
+            northing_index: 999,
+        };
+        assert_eq!("no OSTN15 data for grid node (999, 999)", err.to_string());
+    }
+
+    #[test]
+    fn test_status_from_error() {
+        let err = ConversionError::OutOfBounds {
+            axis: Axis::Northing,
+            value: 1250000.001,
+            min: 0.,
+            max: 1250000.,
+        };
+        assert_eq!(Status::NorthingOutOfBounds, Status::from(err));
+        assert_eq!(6, Status::from(ConversionError::OutsideOstn15 {
+                               easting_index: 999,
+                               northing_index: 999,
+                           }) as u8);
+        assert_eq!(18, Status::from(ConversionError::MissingDatumFlag {
+                                easting_index: 80,
+                                northing_index: 2,
+                            }) as u8);
+    }
+}
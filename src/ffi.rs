@@ -1,7 +1,11 @@
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::BufReader;
+use std::ptr;
 use std::slice;
 
 extern crate libc;
-use self::libc::c_void;
+use self::libc::{c_char, c_void};
 
 #[repr(C)]
 pub struct Array {
@@ -9,15 +13,30 @@ pub struct Array {
     pub len: libc::size_t,
 }
 
-use super::convert_to_bng_threaded_vec;
-use super::convert_to_lonlat_threaded_vec;
-use super::convert_to_osgb36_threaded_vec;
-use super::convert_to_etrs89_threaded_vec;
-use super::convert_etrs89_to_osgb36_threaded_vec;
-use super::convert_etrs89_to_ll_threaded_vec;
-use super::convert_osgb36_to_ll_threaded_vec;
-use super::convert_osgb36_to_etrs89_threaded_vec;
-use super::convert_epsg3857_to_wgs84_threaded_vec;
+use super::NAN;
+use super::ConversionError;
+use super::Crs;
+use super::DatumFlags;
+use super::Ellipsoid;
+use super::VerticalDatum;
+use super::Status;
+use super::Transformer;
+use super::is_supported_epsg_pair;
+use super::is_supported_epsg_pair_3d;
+use super::transform_3d;
+use super::scale_and_convergence_threaded_vec;
+use super::scale_and_convergence_osgb36_threaded_vec;
+use super::convert_to_osgb36_3d_threaded_vec;
+use super::convert_etrs89_to_osgb36_3d_threaded_vec;
+use super::convert_osgb36_to_etrs89_3d_threaded_vec;
+use super::convert_osgb36_to_ll_3d_threaded_vec;
+use super::convert_to_osgb36_3d_threaded_vec_with_datum;
+use super::convert_etrs89_to_osgb36_3d_threaded_vec_with_datum;
+use super::convert_osgb36_to_etrs89_3d_threaded_vec_with_datum;
+use super::convert_ecef_to_osgb36_threaded_vec;
+use super::os_grid_reference_threaded_vec;
+use super::geodesic_inverse_threaded_vec;
+use super::geodesic_direct_threaded_vec;
 
 /// Free memory which Rust has allocated across the FFI boundary (f64 values)
 ///
@@ -55,6 +74,25 @@ pub extern "C" fn drop_float_array(lons: Array, lats: Array) {
     let _: &mut [f64] = lats.into();
 }
 
+/// Free memory which Rust has allocated across the FFI boundary (grid reference strings), as
+/// returned by [`os_grid_reference_threaded`](fn.os_grid_reference_threaded.html)
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn drop_string_array(refs: Array) {
+    if refs.data.is_null() {
+        return;
+    }
+    let refs = unsafe {
+        Box::from_raw(slice::from_raw_parts_mut(refs.data as *mut *mut c_char, refs.len))
+    };
+    for r in refs.iter().filter(|r| !r.is_null()) {
+        let _ = unsafe { CString::from_raw(*r) };
+    }
+}
+
 // Build an Array from &mut[T], so it can be leaked across the FFI boundary
 impl<'a, T> From<&'a mut [T]> for Array {
     fn from(sl: &mut [T]) -> Self {
@@ -73,96 +111,596 @@ impl<'a> From<Array> for &'a mut [f64] {
     }
 }
 
-/// A threaded, FFI-compatible wrapper for `lonlat_bng::convert_osgb36`
+// Build &mut[u8] from an Array of status codes
+impl<'a> From<Array> for &'a mut [u8] {
+    fn from(arr: Array) -> Self {
+        unsafe { slice::from_raw_parts_mut(arr.data as *mut u8, arr.len) }
+    }
+}
+
+// Check that the Arrays passed to an FFI function have the same length. The batch functions
+// panic if they don't, and a panic mustn't unwind across the FFI boundary
+fn lengths_match(arrays: &[&Array]) -> bool {
+    arrays.windows(2).all(|pair| pair[0].len == pair[1].len)
+}
+
+// An empty Array over the caller's data, returned when Arrays differ in length
+fn empty(array: &Array) -> Array {
+    Array {
+        data: array.data,
+        len: 0,
+    }
+}
+
+// Convert arrays in place using a batch datum conversion. If there are no datum flags, all
+// output values are NAN, with a datum flag of 0. If the arrays differ in length, nothing is
+// converted, and empty arrays are returned
+fn datum_arrays<F>(flags: *const DatumFlags,
+                   xs: Array,
+                   ys: Array,
+                   hs: Array,
+                   datums: Array,
+                   func: F)
+                   -> (Array, Array, Array)
+    where F: for<'a> Fn(&'a mut [f64], &'a mut [f64], &'a mut [f64], &'a mut [u8], &DatumFlags)
+                        -> (&'a mut [f64], &'a mut [f64], &'a mut [f64])
+{
+    if !lengths_match(&[&xs, &ys, &hs, &datums]) {
+        return (empty(&xs), empty(&ys), empty(&hs));
+    }
+    let (res_x, res_y, res_h) = match unsafe { flags.as_ref() } {
+        Some(flags) => func(xs.into(), ys.into(), hs.into(), datums.into(), flags),
+        None => {
+            let datums: &mut [u8] = datums.into();
+            for datum in datums.iter_mut() {
+                *datum = VerticalDatum::OutsideModel as u8;
+            }
+            let hs: &mut [f64] = hs.into();
+            for h in hs.iter_mut() {
+                *h = NAN;
+            }
+            let (res_x, res_y) = fill_nan(xs.into(), ys.into());
+            (res_x, res_y, hs)
+        }
+    };
+    (res_x.into(), res_y.into(), res_h.into())
+}
+
+// Convert arrays in place using a Transformer. If there's no Transformer (because an EPSG
+// code is unsupported), all output values are NAN. Every FFI conversion between two
+// coordinate reference systems is built on this
+fn transform_arrays(transformer: Result<Transformer, ConversionError>,
+                    xs: Array,
+                    ys: Array)
+                    -> (Array, Array) {
+    let (res_x, res_y) = match transformer {
+        Ok(transformer) => transformer.transform_threaded_vec(xs.into(), ys.into()),
+        Err(_) => fill_nan(xs.into(), ys.into()),
+    };
+    (res_x.into(), res_y.into())
+}
+
+// As transform_arrays, but also writes a status code for each point. If there's no
+// Transformer, every point's status records why. If the arrays differ in length, nothing is
+// converted, and empty arrays are returned
+fn transform_arrays_with_status(transformer: Result<Transformer, ConversionError>,
+                                xs: Array,
+                                ys: Array,
+                                status: Array)
+                                -> (Array, Array) {
+    if !lengths_match(&[&xs, &ys, &status]) {
+        return (empty(&xs), empty(&ys));
+    }
+    let (res_x, res_y) = match transformer {
+        Ok(transformer) => {
+            transformer.transform_threaded_vec_with_status(xs.into(), ys.into(), status.into())
+        }
+        Err(err) => {
+            let codes: &mut [u8] = status.into();
+            for code in codes.iter_mut() {
+                *code = Status::from(err) as u8;
+            }
+            fill_nan(xs.into(), ys.into())
+        }
+    };
+    (res_x.into(), res_y.into())
+}
+
+// Generate FFI wrappers which convert arrays between two coordinate reference systems,
+// using transform_arrays
+macro_rules! ffi_transform {
+    ($($(#[$attr:meta])* fn $name:ident($x:ident, $y:ident): $from:expr => $to:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[no_mangle]
+            pub extern "C" fn $name($x: Array, $y: Array) -> (Array, Array) {
+                transform_arrays(Ok(Transformer::new($from, $to)), $x, $y)
+            }
+        )*
+    }
+}
+
+// As ffi_transform, using transform_arrays_with_status
+macro_rules! ffi_transform_with_status {
+    ($($(#[$attr:meta])* fn $name:ident($x:ident, $y:ident, $status:ident):
+       $from:expr => $to:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[no_mangle]
+            pub extern "C" fn $name($x: Array, $y: Array, $status: Array) -> (Array, Array) {
+                transform_arrays_with_status(Ok(Transformer::new($from, $to)), $x, $y, $status)
+            }
+        )*
+    }
+}
+
+ffi_transform! {
+    /// A threaded, FFI-compatible wrapper for `lonlat_bng::convert_osgb36`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate libc;
+    /// let lon_vec: Vec<f64> = vec![-2.0183041005533306,
+    ///                              0.95511887434519682,
+    ///                              0.44975855518383501,
+    ///                              -0.096813621191803811,
+    ///                              -0.36807065656416427,
+    ///                              0.63486335458665621];
+    /// let lat_vec: Vec<f64> = vec![54.589097162646141,
+    ///                              51.560873800587828,
+    ///                              50.431429161121699,
+    ///                              54.535021436247419,
+    ///                              50.839059313135706,
+    ///                              55.412189281234419];
+    /// let lon_arr = Array {
+    ///     data: lon_vec.as_ptr() as *const libc::c_void,
+    ///     len: lon_vec.len() as libc::size_t,
+    /// };
+    /// let lat_arr = Array {
+    ///     data: lat_vec.as_ptr() as *const libc::c_void,
+    ///     len: lat_vec.len() as libc::size_t,
+    /// };
+    /// let (eastings, northings) = convert_to_bng_threaded(lon_arr, lat_arr);
+    /// ```
+    /// For an FFI implementation, see the code at [Convertbng](https://github.com/urschrei/convertbng/blob/master/convertbng/util.py).
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_bng_threaded(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+    ///
+    /// # Examples
+    ///
+    /// See [`lonlat_bng::convert_to_bng_threaded`](fn.convert_to_bng_threaded.html)
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_lonlat_threaded(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
+    ///
+    /// # Examples
+    ///
+    /// See [`lonlat_bng::convert_to_bng_threaded`](fn.convert_to_bng_threaded.html) for examples, substituting f64 vectors
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_osgb36_threaded(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples, substituting f64 vectors
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_etrs89_threaded(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Etrs89Grid;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_etrs89_to_osgb36_threaded(eastings, northings):
+        Crs::Etrs89Grid => Crs::Osgb36;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs89_to_ll.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_etrs89_to_ll_threaded(eastings, northings):
+        Crs::Etrs89Grid => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_ll_threaded(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_etrs89_threaded(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89Grid;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_epsg3857_to_wgs84_threaded(x, y):
+        Crs::WebMercator => Crs::Wgs84;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_wgs84_to_epsg3857`](fn.convert_wgs84_to_epsg3857.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_wgs84_to_epsg3857_threaded(longitudes, latitudes):
+        Crs::Wgs84 => Crs::WebMercator;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_epsg3857`](fn.convert_osgb36_to_epsg3857.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_epsg3857_threaded(eastings, northings):
+        Crs::Osgb36 => Crs::WebMercator;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_epsg3857_to_osgb36`](fn.convert_epsg3857_to_osgb36.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_epsg3857_to_osgb36_threaded(x, y):
+        Crs::WebMercator => Crs::Osgb36;
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::scale_and_convergence`](fn.scale_and_convergence.html)
+///
+/// Returns arrays of point scale factors and meridian convergences (decimal degrees)
 ///
 /// # Examples
 ///
-/// ```
-/// extern crate libc;
-/// let lon_vec: Vec<f64> = vec![-2.0183041005533306,
-///                              0.95511887434519682,
-///                              0.44975855518383501,
-///                              -0.096813621191803811,
-///                              -0.36807065656416427,
-///                              0.63486335458665621];
-/// let lat_vec: Vec<f64> = vec![54.589097162646141,
-///                              51.560873800587828,
-///                              50.431429161121699,
-///                              54.535021436247419,
-///                              50.839059313135706,
-///                              55.412189281234419];
-/// let lon_arr = Array {
-///     data: lon_vec.as_ptr() as *const libc::c_void,
-///     len: lon_vec.len() as libc::size_t,
-/// };
-/// let lat_arr = Array {
-///     data: lat_vec.as_ptr() as *const libc::c_void,
-///     len: lat_vec.len() as libc::size_t,
-/// };
-/// let (eastings, northings) = convert_to_bng_threaded(lon_arr, lat_arr);
-/// ```
-/// For an FFI implementation, see the code at [Convertbng](https://github.com/urschrei/convertbng/blob/master/convertbng/util.py).
+/// See `lonlat_bng::convert_to_bng_threaded` for examples
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_to_bng_threaded(longitudes: Array, latitudes: Array) -> (Array, Array) {
-    let (res_lon, res_lat) = convert_to_bng_threaded_vec(longitudes.into(), latitudes.into());
-    (res_lon.into(), res_lat.into())
+pub extern "C" fn scale_and_convergence_threaded(longitudes: Array,
+                                                 latitudes: Array)
+                                                 -> (Array, Array) {
+    let (scale, convergence) = scale_and_convergence_threaded_vec(longitudes.into(),
+                                                                  latitudes.into());
+    (scale.into(), convergence.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::scale_and_convergence_osgb36`](fn.scale_and_convergence_osgb36.html)
+///
+/// Returns arrays of point scale factors and meridian convergences (decimal degrees)
 ///
 /// # Examples
 ///
-/// See [`lonlat_bng::convert_to_bng_threaded`](fn.convert_to_bng_threaded.html)
+/// See `lonlat_bng::convert_to_bng_threaded` for examples
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_to_lonlat_threaded(eastings: Array, northings: Array) -> (Array, Array) {
-    let (res_eastings, res_northings) = convert_to_lonlat_threaded_vec(eastings.into(),
-                                                                       northings.into());
-    (res_eastings.into(), res_northings.into())
+pub extern "C" fn scale_and_convergence_osgb36_threaded(eastings: Array,
+                                                        northings: Array)
+                                                        -> (Array, Array) {
+    let (scale, convergence) = scale_and_convergence_osgb36_threaded_vec(eastings.into(),
+                                                                         northings.into());
+    (scale.into(), convergence.into())
+}
+
+ffi_transform! {
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_itm`](fn.convert_itm.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_itm_threaded(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Itm;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_itm_to_ll`](fn.convert_itm_to_ll.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_itm_to_ll_threaded(eastings, northings):
+        Crs::Itm => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_irish_grid`](fn.convert_irish_grid.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_irish_grid_threaded(longitudes, latitudes):
+        Crs::Etrs89 => Crs::IrishGrid;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_irish_grid_to_ll`](fn.convert_irish_grid_to_ll.html)
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_irish_grid_to_ll_threaded(eastings, northings):
+        Crs::IrishGrid => Crs::Etrs89;
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
+ffi_transform_with_status! {
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_osgb36_threaded_with_status(longitudes, latitudes, status):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_to_etrs89_threaded_with_status(longitudes, latitudes, status):
+        Crs::Etrs89 => Crs::Etrs89Grid;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_etrs89_to_osgb36_threaded_with_status(eastings, northings, status):
+        Crs::Etrs89Grid => Crs::Osgb36;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs89_to_ll.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_etrs89_to_ll_threaded_with_status(eastings, northings, status):
+        Crs::Etrs89Grid => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_etrs89_threaded_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::Etrs89Grid;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_ll_threaded_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_epsg3857_to_wgs84_threaded_with_status(x, y, status):
+        Crs::WebMercator => Crs::Wgs84;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_wgs84_to_epsg3857`](fn.convert_wgs84_to_epsg3857.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_wgs84_to_epsg3857_threaded_with_status(longitudes, latitudes, status):
+        Crs::Wgs84 => Crs::WebMercator;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_epsg3857`](fn.convert_osgb36_to_epsg3857.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_osgb36_to_epsg3857_threaded_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::WebMercator;
+
+    /// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_epsg3857_to_osgb36`](fn.convert_epsg3857_to_osgb36.html), which
+    /// writes a [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+    ///
+    /// If `status` and the input arrays differ in length, nothing is converted, and empty
+    /// arrays are returned.
+    ///
+    /// # Examples
+    ///
+    /// See `lonlat_bng::convert_to_bng_threaded` for examples
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+    fn convert_epsg3857_to_osgb36_threaded_with_status(x, y, status):
+        Crs::WebMercator => Crs::Osgb36;
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_3d`](fn.convert_osgb36_3d.html)
+///
+/// Returns arrays of Eastings, Northings, and ODN heights
 ///
 /// # Examples
 ///
-/// See [`lonlat_bng::convert_to_bng_threaded`](fn.convert_to_bng_threaded.html) for examples, substituting f64 vectors
+/// See `lonlat_bng::convert_to_bng_threaded` for examples
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_to_osgb36_threaded(longitudes: Array,
-                                             latitudes: Array)
-                                             -> (Array, Array) {
-    let (res_lon, res_lat) = convert_to_osgb36_threaded_vec(longitudes.into(), latitudes.into());
-    (res_lon.into(), res_lat.into())
+pub extern "C" fn convert_to_osgb36_3d_threaded(longitudes: Array,
+                                                latitudes: Array,
+                                                heights: Array)
+                                                -> (Array, Array, Array) {
+    let (res_lon, res_lat, res_h) =
+        convert_to_osgb36_3d_threaded_vec(longitudes.into(), latitudes.into(), heights.into());
+    (res_lon.into(), res_lat.into(), res_h.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_osgb36_3d`](fn.convert_etrs89_to_osgb36_3d.html)
+///
+/// Returns arrays of Eastings, Northings, and ODN heights
 ///
 /// # Examples
 ///
-/// See `lonlat_bng::convert_to_bng_threaded` for examples, substituting f64 vectors
+/// See `lonlat_bng::convert_to_bng_threaded` for examples
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_to_etrs89_threaded(longitudes: Array,
-                                             latitudes: Array)
-                                             -> (Array, Array) {
-    let (res_lon, res_lat) = convert_to_etrs89_threaded_vec(longitudes.into(), latitudes.into());
-    (res_lon.into(), res_lat.into())
+pub extern "C" fn convert_etrs89_to_osgb36_3d_threaded(eastings: Array,
+                                                       northings: Array,
+                                                       heights: Array)
+                                                       -> (Array, Array, Array) {
+    let (res_e, res_n, res_h) =
+        convert_etrs89_to_osgb36_3d_threaded_vec(eastings.into(), northings.into(), heights.into());
+    (res_e.into(), res_n.into(), res_h.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_etrs89_3d`](fn.convert_osgb36_to_etrs89_3d.html)
+///
+/// Returns arrays of ETRS89 Eastings, Northings, and ellipsoidal heights
 ///
 /// # Examples
 ///
@@ -172,15 +710,18 @@ pub extern "C" fn convert_to_etrs89_threaded(longitudes: Array,
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_etrs89_to_osgb36_threaded(eastings: Array,
-                                                    northings: Array)
-                                                    -> (Array, Array) {
-    let (res_eastings, res_northings) = convert_etrs89_to_osgb36_threaded_vec(eastings.into(),
-                                                                              northings.into());
-    (res_eastings.into(), res_northings.into())
+pub extern "C" fn convert_osgb36_to_etrs89_3d_threaded(eastings: Array,
+                                                       northings: Array,
+                                                       heights: Array)
+                                                       -> (Array, Array, Array) {
+    let (res_e, res_n, res_h) =
+        convert_osgb36_to_etrs89_3d_threaded_vec(eastings.into(), northings.into(), heights.into());
+    (res_e.into(), res_n.into(), res_h.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs89_to_ll.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll_3d`](fn.convert_osgb36_to_ll_3d.html)
+///
+/// Returns arrays of Longitudes, Latitudes, and ETRS89 ellipsoidal heights
 ///
 /// # Examples
 ///
@@ -190,15 +731,129 @@ pub extern "C" fn convert_etrs89_to_osgb36_threaded(eastings: Array,
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_etrs89_to_ll_threaded(eastings: Array,
-                                                northings: Array)
-                                                -> (Array, Array) {
-    let (res_eastings, res_northings) = convert_etrs89_to_ll_threaded_vec(eastings.into(),
-                                                                          northings.into());
-    (res_eastings.into(), res_northings.into())
+pub extern "C" fn convert_osgb36_to_ll_3d_threaded(eastings: Array,
+                                                   northings: Array,
+                                                   heights: Array)
+                                                   -> (Array, Array, Array) {
+    let (res_lon, res_lat, res_h) =
+        convert_osgb36_to_ll_3d_threaded_vec(eastings.into(), northings.into(), heights.into());
+    (res_lon.into(), res_lat.into(), res_h.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+/// Load OSGM15 vertical datum flags from the official `OSTN15_OSGM15_DataFile.txt` at `path`,
+/// for use by the `*_with_datum` functions
+///
+/// Returns a null pointer if the file can't be read. The flags must be freed using
+/// [`drop_datum_flags`](fn.drop_datum_flags.html)
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub unsafe extern "C" fn datum_flags_from_file(path: *const c_char) -> *mut DatumFlags {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(path).to_str() {
+        Ok(path) => path,
+        Err(_) => return ptr::null_mut(),
+    };
+    File::open(path)
+        .and_then(|file| DatumFlags::from_reader(BufReader::new(file)))
+        .map_or(ptr::null_mut(), |flags| Box::into_raw(Box::new(flags)))
+}
+
+/// Free datum flags which were loaded by [`datum_flags_from_file`](fn.datum_flags_from_file.html)
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub unsafe extern "C" fn drop_datum_flags(flags: *mut DatumFlags) {
+    if flags.is_null() {
+        return;
+    }
+    let _ = Box::from_raw(flags);
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_3d_with_datum`](fn.convert_osgb36_3d_with_datum.html)
+///
+/// Returns arrays of Eastings, Northings, and ODN heights, and writes the
+/// [`VerticalDatum`](enum.VerticalDatum.html) flag of each point into the `u8` array `datums`.
+/// If `flags` is null, all output values are `NAN`, with a flag of `0`. If the arrays differ in
+/// length, nothing is converted, and empty arrays are returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn convert_to_osgb36_3d_threaded_with_datum(flags: *const DatumFlags,
+                                                           longitudes: Array,
+                                                           latitudes: Array,
+                                                           heights: Array,
+                                                           datums: Array)
+                                                           -> (Array, Array, Array) {
+    datum_arrays(flags,
+                 longitudes,
+                 latitudes,
+                 heights,
+                 datums,
+                 convert_to_osgb36_3d_threaded_vec_with_datum)
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_etrs89_to_osgb36_3d_with_datum`](fn.convert_etrs89_to_osgb36_3d_with_datum.html)
+///
+/// Returns arrays of Eastings, Northings, and ODN heights, and writes the
+/// [`VerticalDatum`](enum.VerticalDatum.html) flag of each point into the `u8` array `datums`.
+/// If `flags` is null, all output values are `NAN`, with a flag of `0`. If the arrays differ in
+/// length, nothing is converted, and empty arrays are returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn convert_etrs89_to_osgb36_3d_threaded_with_datum(flags: *const DatumFlags,
+                                                                  eastings: Array,
+                                                                  northings: Array,
+                                                                  heights: Array,
+                                                                  datums: Array)
+                                                                  -> (Array, Array, Array) {
+    datum_arrays(flags,
+                 eastings,
+                 northings,
+                 heights,
+                 datums,
+                 convert_etrs89_to_osgb36_3d_threaded_vec_with_datum)
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_etrs89_3d_with_datum`](fn.convert_osgb36_to_etrs89_3d_with_datum.html)
+///
+/// Returns arrays of ETRS89 Eastings, Northings, and ellipsoidal heights, and writes the
+/// [`VerticalDatum`](enum.VerticalDatum.html) flag to which each input height refers into the
+/// `u8` array `datums`. If `flags` is null, all output values are `NAN`, with a flag of `0`.
+/// If the arrays differ in length, nothing is converted, and empty arrays are returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn convert_osgb36_to_etrs89_3d_threaded_with_datum(flags: *const DatumFlags,
+                                                                  eastings: Array,
+                                                                  northings: Array,
+                                                                  heights: Array,
+                                                                  datums: Array)
+                                                                  -> (Array, Array, Array) {
+    datum_arrays(flags,
+                 eastings,
+                 northings,
+                 heights,
+                 datums,
+                 convert_osgb36_to_etrs89_3d_threaded_vec_with_datum)
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_ecef_to_osgb36`](fn.convert_ecef_to_osgb36.html)
+///
+/// Returns arrays of Eastings, Northings, and ODN heights
 ///
 /// # Examples
 ///
@@ -208,15 +863,76 @@ pub extern "C" fn convert_etrs89_to_ll_threaded(eastings: Array,
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_osgb36_to_ll_threaded(eastings: Array,
-                                                northings: Array)
-                                                -> (Array, Array) {
-    let (res_eastings, res_northings) = convert_osgb36_to_ll_threaded_vec(eastings.into(),
-                                                                          northings.into());
-    (res_eastings.into(), res_northings.into())
+pub extern "C" fn convert_ecef_to_osgb36_threaded(xs: Array,
+                                                  ys: Array,
+                                                  zs: Array)
+                                                  -> (Array, Array, Array) {
+    let (res_e, res_n, res_h) =
+        convert_ecef_to_osgb36_threaded_vec(xs.into(), ys.into(), zs.into());
+    (res_e.into(), res_n.into(), res_h.into())
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::geodesic_inverse`](fn.geodesic_inverse.html)
+/// on the GRS80 ellipsoid, which solves the problem for each pair of ETRS89 or WGS84 points
+///
+/// Returns arrays of distances, azimuths and back azimuths, or `NAN` values for pairs which
+/// can't be solved. If the arrays differ in length, nothing is solved, and empty arrays are
+/// returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn geodesic_inverse_threaded(lons1: Array,
+                                            lats1: Array,
+                                            lons2: Array,
+                                            lats2: Array)
+                                            -> (Array, Array, Array) {
+    if !lengths_match(&[&lons1, &lats1, &lons2, &lats2]) {
+        return (empty(&lons1), empty(&lats1), empty(&lons2));
+    }
+    let lats2: &mut [f64] = lats2.into();
+    let (distances, azimuths, back_azimuths) = geodesic_inverse_threaded_vec(lons1.into(),
+                                                                             lats1.into(),
+                                                                             lons2.into(),
+                                                                             lats2,
+                                                                             &Ellipsoid::GRS80);
+    (distances.into(), azimuths.into(), back_azimuths.into())
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::geodesic_direct`](fn.geodesic_direct.html)
+/// on the GRS80 ellipsoid, for ETRS89 or WGS84 starting points
+///
+/// Returns arrays of the longitudes, latitudes and back azimuths of the points reached, or
+/// `NAN` values for points which can't be reached. If the arrays differ in length, nothing is
+/// solved, and empty arrays are returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn geodesic_direct_threaded(longitudes: Array,
+                                           latitudes: Array,
+                                           azimuths: Array,
+                                           distances: Array)
+                                           -> (Array, Array, Array) {
+    if !lengths_match(&[&longitudes, &latitudes, &azimuths, &distances]) {
+        return (empty(&longitudes), empty(&latitudes), empty(&azimuths));
+    }
+    let distances: &mut [f64] = distances.into();
+    let (lons, lats, back_azimuths) = geodesic_direct_threaded_vec(longitudes.into(),
+                                                                   latitudes.into(),
+                                                                   azimuths.into(),
+                                                                   distances,
+                                                                   &Ellipsoid::GRS80);
+    (lons.into(), lats.into(), back_azimuths.into())
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::transform`](fn.transform.html),
+/// which converts coordinates between two EPSG codes
+///
+/// If either code is unsupported, all output values are `NAN`.
+/// Use [`epsg_pair_supported`](fn.epsg_pair_supported.html) to check ahead of time.
 ///
 /// # Examples
 ///
@@ -226,25 +942,145 @@ pub extern "C" fn convert_osgb36_to_ll_threaded(eastings: Array,
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_osgb36_to_etrs89_threaded(eastings: Array,
-                                                    northings: Array)
-                                                    -> (Array, Array) {
-    let (res_eastings, res_northings) = convert_osgb36_to_etrs89_threaded_vec(eastings.into(),
-                                                                              northings.into());
-    (res_eastings.into(), res_northings.into())
+pub extern "C" fn convert_epsg_threaded(from_epsg: u32,
+                                        to_epsg: u32,
+                                        xs: Array,
+                                        ys: Array)
+                                        -> (Array, Array) {
+    transform_arrays(Transformer::from_epsg(from_epsg, to_epsg), xs, ys)
 }
 
-/// A threaded, FFI-compatible wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html)
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::os_grid_reference`](fn.os_grid_reference.html)
+///
+/// Returns an array of pointers to NUL-terminated grid reference strings, with a null pointer
+/// for each point which couldn't be written. The array must be freed using
+/// [`drop_string_array`](fn.drop_string_array.html)
 ///
 /// # Examples
 ///
-/// See `lonlat_bng::convert_to_bng_threaded` for examples
+/// ```
+/// # extern crate libc;
+/// # extern crate lonlat_bng;
+/// use std::ffi::CStr;
+/// use lonlat_bng::{Array, drop_string_array, os_grid_reference_threaded};
+/// # fn main() {
+/// let e_vec: Vec<f64> = vec![516276., 700000.];
+/// let n_vec: Vec<f64> = vec![173141., 0.];
+/// let e_arr = Array {
+///     data: e_vec.as_ptr() as *const libc::c_void,
+///     len: e_vec.len() as libc::size_t,
+/// };
+/// let n_arr = Array {
+///     data: n_vec.as_ptr() as *const libc::c_void,
+///     len: n_vec.len() as libc::size_t,
+/// };
+/// let refs = os_grid_reference_threaded(e_arr, n_arr, 10, true);
+/// let ptrs = unsafe {
+///     std::slice::from_raw_parts(refs.data as *const *const libc::c_char, refs.len)
+/// };
+/// assert_eq!("TQ 16276 73141", unsafe { CStr::from_ptr(ptrs[0]) }.to_str().unwrap());
+/// assert!(ptrs[1].is_null());
+/// drop_string_array(refs);
+/// # }
+/// ```
 ///
 /// # Safety
 ///
 /// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
 #[no_mangle]
-pub extern "C" fn convert_epsg3857_to_wgs84_threaded(x: Array, y: Array) -> (Array, Array) {
-    let (res_x, res_y) = convert_epsg3857_to_wgs84_threaded_vec(x.into(), y.into());
-    (res_x.into(), res_y.into())
+pub extern "C" fn os_grid_reference_threaded(eastings: Array,
+                                             northings: Array,
+                                             digits: u8,
+                                             spaces: bool)
+                                             -> Array {
+    let eastings: &mut [f64] = eastings.into();
+    let northings: &mut [f64] = northings.into();
+    let refs: Box<[*mut c_char]> =
+        os_grid_reference_threaded_vec(eastings, northings, digits as usize, spaces)
+            .into_iter()
+            .map(|r| {
+                r.ok()
+                    .and_then(|s| CString::new(s).ok())
+                    .map_or(ptr::null_mut(), CString::into_raw)
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+    let len = refs.len();
+    Array {
+        data: Box::into_raw(refs) as *mut *mut c_char as *const c_void,
+        len: len as libc::size_t,
+    }
+}
+
+/// As [`convert_epsg_threaded`](fn.convert_epsg_threaded.html), but also writes a
+/// [`Status`](enum.Status.html) code for each point into the `u8` array `status`
+///
+/// If `status` and the input arrays differ in length, nothing is converted, and empty arrays
+/// are returned
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn convert_epsg_threaded_with_status(from_epsg: u32,
+                                                    to_epsg: u32,
+                                                    xs: Array,
+                                                    ys: Array,
+                                                    status: Array)
+                                                    -> (Array, Array) {
+    transform_arrays_with_status(Transformer::from_epsg(from_epsg, to_epsg), xs, ys, status)
+}
+
+/// Check whether [`convert_epsg_threaded`](fn.convert_epsg_threaded.html) can convert
+/// between two EPSG codes
+#[no_mangle]
+pub extern "C" fn epsg_pair_supported(from_epsg: u32, to_epsg: u32) -> bool {
+    is_supported_epsg_pair(from_epsg, to_epsg)
+}
+
+/// A threaded, FFI-compatible wrapper for [`lonlat_bng::transform_3d`](fn.transform_3d.html),
+/// which converts coordinates and heights between two EPSG codes, e.g. from 4258 (ETRS89, with
+/// ellipsoidal heights) to 7405 (British National Grid + ODN height)
+///
+/// If either code is unsupported, all output values are `NAN`.
+/// Use [`epsg_pair_supported_3d`](fn.epsg_pair_supported_3d.html) to check ahead of time.
+///
+/// # Safety
+///
+/// This function is unsafe because it accesses a raw pointer which could contain arbitrary data
+#[no_mangle]
+pub extern "C" fn convert_epsg_3d_threaded(from_epsg: u32,
+                                           to_epsg: u32,
+                                           xs: Array,
+                                           ys: Array,
+                                           hs: Array)
+                                           -> (Array, Array, Array) {
+    let xs: &mut [f64] = xs.into();
+    let ys: &mut [f64] = ys.into();
+    let hs: &mut [f64] = hs.into();
+    if transform_3d(from_epsg, to_epsg, xs, ys, hs).is_err() {
+        fill_nan(xs, ys);
+        for h in hs.iter_mut() {
+            *h = NAN;
+        }
+    }
+    (xs.into(), ys.into(), hs.into())
+}
+
+/// Check whether [`convert_epsg_3d_threaded`](fn.convert_epsg_3d_threaded.html) can convert
+/// between two EPSG codes
+#[no_mangle]
+pub extern "C" fn epsg_pair_supported_3d(from_epsg: u32, to_epsg: u32) -> bool {
+    is_supported_epsg_pair_3d(from_epsg, to_epsg)
+}
+
+// Used when a whole batch can't be converted
+fn fill_nan<'a>(xs: &'a mut [f64], ys: &'a mut [f64]) -> (&'a mut [f64], &'a mut [f64]) {
+    for x in xs.iter_mut() {
+        *x = NAN;
+    }
+    for y in ys.iter_mut() {
+        *y = NAN;
+    }
+    (xs, ys)
 }
@@ -0,0 +1,1130 @@
+//! This module solves the inverse and direct geodesic problems on an ellipsoid
+//!
+//! The algorithms are those of C. F. F. Karney,
+//! [Algorithms for geodesics](https://doi.org/10.1007/s00190-012-0578-z), J. Geodesy 87 (2013),
+//! using sixth-order series, as in GeographicLib. They are accurate to around 15 nm for any
+//! pair of points on the GRS80 or Airy 1830 ellipsoids, including nearly antipodal ones.
+//!
+//! Azimuths are in decimal degrees, clockwise from true north, in the range [0, 360).
+//! The back azimuth is the azimuth from the second point back along the geodesic
+//! to the first.
+use std::f64;
+
+use rayon::prelude::*;
+
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use utils::{check, normalise_bearing};
+
+const ORDER: usize = 6;
+const NC3X: usize = 15;
+const NC4X: usize = 21;
+const MAXIT1: usize = 20;
+const MAXIT2: usize = MAXIT1 + 53 + 10;
+const TOL0: f64 = f64::EPSILON;
+
+// The quantities derived from an ellipsoid which are used by both problems
+struct Geodesic {
+    a: f64,
+    f: f64,
+    f1: f64,
+    e2: f64,
+    ep2: f64,
+    n: f64,
+    b: f64,
+    // The square of the authalic radius
+    c2: f64,
+    etol2: f64,
+    a3x: [f64; ORDER],
+    c3x: [f64; NC3X],
+    c4x: [f64; NC4X],
+}
+
+fn tiny() -> f64 {
+    f64::MIN_POSITIVE.sqrt()
+}
+
+fn tol1() -> f64 {
+    200. * TOL0
+}
+
+fn tol2() -> f64 {
+    TOL0.sqrt()
+}
+
+fn tolb() -> f64 {
+    TOL0 * tol2()
+}
+
+fn xthresh() -> f64 {
+    1000. * tol2()
+}
+
+// Evaluate a polynomial of degree n, whose coefficients are given from the highest degree
+fn polyval(n: isize, p: &[f64], x: f64) -> f64 {
+    if n < 0 {
+        return 0.;
+    }
+    p[1..(n as usize + 1)].iter().fold(p[0], |y, c| y * x + c)
+}
+
+fn norm2(s: &mut f64, c: &mut f64) {
+    let r = s.hypot(*c);
+    *s /= r;
+    *c /= r;
+}
+
+// Round very small values, so that subsequent differences are exact
+fn ang_round(x: f64) -> f64 {
+    let z = 1. / 16.;
+    let y = x.abs();
+    let y = if y < z { z - (z - y) } else { y };
+    if x < 0. { -y } else { y }
+}
+
+fn ang_normalise(x: f64) -> f64 {
+    let x = x % 360.;
+    if x <= -180. {
+        x + 360.
+    } else if x > 180. {
+        x - 360.
+    } else {
+        x
+    }
+}
+
+// Whether an edge crosses the prime meridian eastwards (1) or westwards (-1)
+fn transit(lon1: f64, lon2: f64) -> i32 {
+    let lon1 = ang_normalise(lon1);
+    let lon2 = ang_normalise(lon2);
+    let lon12 = ang_normalise(lon2 - lon1);
+    if lon1 <= 0. && lon2 > 0. && lon12 > 0. {
+        1
+    } else if lon2 <= 0. && lon1 > 0. && lon12 < 0. {
+        -1
+    } else {
+        0
+    }
+}
+
+// The sine and cosine of an angle in degrees, exact at multiples of 90°
+fn sincosd(x: f64) -> (f64, f64) {
+    let q = (x / 90.).round();
+    let r = (x - q * 90.).to_radians();
+    let (s, c) = r.sin_cos();
+    let (sinx, cosx) = match ((q as i64 % 4) + 4) % 4 {
+        0 => (s, c),
+        1 => (c, -s),
+        2 => (-s, -c),
+        _ => (-c, s),
+    };
+    (sinx + 0., cosx + 0.)
+}
+
+fn atan2d(y: f64, x: f64) -> f64 {
+    y.atan2(x).to_degrees()
+}
+
+// Evaluate a sum of sin (if sinp) or cos series, using Clenshaw summation.
+// The coefficients are c[1..n+1] for a sine series, and c[0..n] for a cosine series
+fn sin_cos_series(sinp: bool, sinx: f64, cosx: f64, c: &[f64], n: usize) -> f64 {
+    let mut k = n + if sinp { 1 } else { 0 };
+    let ar = 2. * (cosx - sinx) * (cosx + sinx);
+    let mut y0 = if n & 1 == 1 {
+        k -= 1;
+        c[k]
+    } else {
+        0.
+    };
+    let mut y1 = 0.;
+    for _ in 0..n / 2 {
+        k -= 1;
+        y1 = ar * y0 - y1 + c[k];
+        k -= 1;
+        y0 = ar * y1 - y0 + c[k];
+    }
+    if sinp {
+        2. * sinx * cosx * y0
+    } else {
+        cosx * (y0 - y1)
+    }
+}
+
+// The series for the distance integral I1
+fn a1m1f(eps: f64) -> f64 {
+    let coeff = [1., 4., 64., 0., 256.];
+    let m = ORDER as isize / 2;
+    let t = polyval(m, &coeff, eps * eps) / coeff[m as usize + 1];
+    (t + eps) / (1. - eps)
+}
+
+fn c1f(eps: f64, c: &mut [f64]) {
+    let coeff = [-1., 6., -16., 32., -9., 64., -128., 2048., 9., -16., 768., 3., -5., 512.,
+                 -7., 1280., -7., 2048.];
+    let eps2 = eps * eps;
+    let mut d = eps;
+    let mut o = 0;
+    for l in 1..ORDER + 1 {
+        let m = ((ORDER - l) / 2) as isize;
+        c[l] = d * polyval(m, &coeff[o..], eps2) / coeff[o + m as usize + 1];
+        o += m as usize + 2;
+        d *= eps;
+    }
+}
+
+// The reverted series, giving sigma in terms of tau
+fn c1pf(eps: f64, c: &mut [f64]) {
+    let coeff = [205., -432., 768., 1536., 4005., -4736., 3840., 12288., -225., 116., 384.,
+                 -7173., 2695., 7680., 3467., 7680., 38081., 61440.];
+    let eps2 = eps * eps;
+    let mut d = eps;
+    let mut o = 0;
+    for l in 1..ORDER + 1 {
+        let m = ((ORDER - l) / 2) as isize;
+        c[l] = d * polyval(m, &coeff[o..], eps2) / coeff[o + m as usize + 1];
+        o += m as usize + 2;
+        d *= eps;
+    }
+}
+
+// The series for the reduced length integral I2
+fn a2m1f(eps: f64) -> f64 {
+    let coeff = [-11., -28., -192., 0., 256.];
+    let m = ORDER as isize / 2;
+    let t = polyval(m, &coeff, eps * eps) / coeff[m as usize + 1];
+    (t - eps) / (1. + eps)
+}
+
+fn c2f(eps: f64, c: &mut [f64]) {
+    let coeff = [1., 2., 16., 32., 35., 64., 384., 2048., 15., 80., 768., 7., 35., 512., 63.,
+                 1280., 77., 2048.];
+    let eps2 = eps * eps;
+    let mut d = eps;
+    let mut o = 0;
+    for l in 1..ORDER + 1 {
+        let m = ((ORDER - l) / 2) as isize;
+        c[l] = d * polyval(m, &coeff[o..], eps2) / coeff[o + m as usize + 1];
+        o += m as usize + 2;
+        d *= eps;
+    }
+}
+
+// The results of the Lengths calculation
+struct Lengths {
+    s12b: f64,
+    m12b: f64,
+}
+
+impl Geodesic {
+    fn new(ellipsoid: &Ellipsoid) -> Geodesic {
+        let a = ellipsoid.semi_major();
+        let f = ellipsoid.flattening();
+        let f1 = 1. - f;
+        let e2 = ellipsoid.e2();
+        let mut g = Geodesic {
+            a: a,
+            f: f,
+            f1: f1,
+            e2: e2,
+            ep2: e2 / (f1 * f1),
+            n: f / (2. - f),
+            b: a * f1,
+            c2: (a.powi(2) +
+                 (a * f1).powi(2) * if e2 == 0. {
+                1.
+            } else {
+                e2.sqrt().atanh() / e2.sqrt()
+            }) / 2.,
+            etol2: 0.1 * tol2() /
+                   ((f.abs().max(0.001) * (1. - f / 2.).min(1.) / 2.).sqrt()),
+            a3x: [0.; ORDER],
+            c3x: [0.; NC3X],
+            c4x: [0.; NC4X],
+        };
+        g.a3coeff();
+        g.c3coeff();
+        g.c4coeff();
+        g
+    }
+
+    fn a3coeff(&mut self) {
+        let coeff = [-3., 128., -2., -3., 64., -1., -3., -1., 16., 3., -1., -2., 8., 1., -1.,
+                     2., 1., 1.];
+        let mut o = 0;
+        for (k, j) in (0..ORDER).rev().enumerate() {
+            let m = (ORDER - j - 1).min(j);
+            self.a3x[k] = polyval(m as isize, &coeff[o..], self.n) / coeff[o + m + 1];
+            o += m + 2;
+        }
+    }
+
+    fn c3coeff(&mut self) {
+        let coeff = [3., 128., 2., 5., 128., -1., 3., 3., 64., -1., 0., 1., 8., -1., 1., 4., 5.,
+                     256., 1., 3., 128., -3., -2., 3., 64., 1., -3., 2., 32., 7., 512., -10.,
+                     9., 384., 5., -9., 5., 192., 7., 512., -14., 7., 512., 21., 2560.];
+        let mut o = 0;
+        let mut k = 0;
+        for l in 1..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = (ORDER - j - 1).min(j);
+                self.c3x[k] = polyval(m as isize, &coeff[o..], self.n) / coeff[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+    }
+
+    // The coefficients of the series for the area integral I4
+    fn c4coeff(&mut self) {
+        let coeff = [97., 15015., 1088., 156., 45045., -224., -4784., 1573., 45045., -10656.,
+                     14144., -4576., -858., 45045., 64., 624., -4576., 6864., -3003., 15015.,
+                     100., 208., 572., 3432., -12012., 30030., 45045., 1., 9009., -2944., 468.,
+                     135135., 5792., 1040., -1287., 135135., 5952., -11648., 9152., -2574.,
+                     135135., -64., -624., 4576., -6864., 3003., 135135., 8., 10725., 1856.,
+                     -936., 225225., -8448., 4992., -1144., 225225., -1440., 4160., -4576.,
+                     1716., 225225., -136., 63063., 1024., -208., 105105., 3584., -3328., 1144.,
+                     315315., -128., 135135., -2560., 832., 405405., 128., 99099.];
+        let mut o = 0;
+        let mut k = 0;
+        for l in 0..ORDER {
+            for j in (l..ORDER).rev() {
+                let m = ORDER - j - 1;
+                self.c4x[k] = polyval(m as isize, &coeff[o..], self.n) / coeff[o + m + 1];
+                k += 1;
+                o += m + 2;
+            }
+        }
+    }
+
+    fn a3f(&self, eps: f64) -> f64 {
+        polyval(ORDER as isize - 1, &self.a3x, eps)
+    }
+
+    fn c3f(&self, eps: f64, c: &mut [f64]) {
+        let mut mult = 1.;
+        let mut o = 0;
+        for l in 1..ORDER {
+            let m = ORDER - l - 1;
+            mult *= eps;
+            c[l] = mult * polyval(m as isize, &self.c3x[o..], eps);
+            o += m + 1;
+        }
+    }
+
+    fn c4f(&self, eps: f64, c: &mut [f64]) {
+        let mut mult = 1.;
+        let mut o = 0;
+        for l in 0..ORDER {
+            let m = ORDER - l - 1;
+            c[l] = mult * polyval(m as isize, &self.c4x[o..], eps);
+            o += m + 1;
+            mult *= eps;
+        }
+    }
+
+    // The distance and reduced length along a geodesic, scaled by b
+    // Each point is given as (sin σ, cos σ, dn)
+    fn lengths(&self,
+               eps: f64,
+               sig12: f64,
+               (ssig1, csig1, dn1): (f64, f64, f64),
+               (ssig2, csig2, dn2): (f64, f64, f64))
+               -> Lengths {
+        let mut ca = [0.; ORDER + 1];
+        let mut cb = [0.; ORDER + 1];
+        c1f(eps, &mut ca);
+        c2f(eps, &mut cb);
+        let a1 = 1. + a1m1f(eps);
+        let a2 = 1. + a2m1f(eps);
+        let m0 = a1 - a2;
+        let b1 = sin_cos_series(true, ssig2, csig2, &ca, ORDER) -
+                 sin_cos_series(true, ssig1, csig1, &ca, ORDER);
+        let b2 = sin_cos_series(true, ssig2, csig2, &cb, ORDER) -
+                 sin_cos_series(true, ssig1, csig1, &cb, ORDER);
+        let j12 = m0 * sig12 + (a1 * b1 - a2 * b2);
+        Lengths {
+            s12b: a1 * (sig12 + b1),
+            m12b: dn2 * (csig1 * ssig2) - dn1 * (ssig1 * csig2) - csig1 * csig2 * j12,
+        }
+    }
+
+    // Solve the astroid equation for k, giving a starting point for nearly antipodal points
+    fn astroid(x: f64, y: f64) -> f64 {
+        let p = x * x;
+        let q = y * y;
+        let r = (p + q - 1.) / 6.;
+        if q == 0. && r <= 0. {
+            return 0.;
+        }
+        let s = p * q / 4.;
+        let r2 = r * r;
+        let r3 = r * r2;
+        let disc = s * (s + 2. * r3);
+        let mut u = r;
+        if disc >= 0. {
+            let mut t3 = s + r3;
+            t3 += if t3 < 0. { -disc.sqrt() } else { disc.sqrt() };
+            let t = t3.cbrt();
+            u += t + if t != 0. { r2 / t } else { 0. };
+        } else {
+            let ang = (-disc).sqrt().atan2(-(s + r3));
+            u += 2. * r * (ang / 3.).cos();
+        }
+        let v = (u * u + q).sqrt();
+        let uv = if u < 0. { q / (v - u) } else { u + v };
+        let w = (uv - q) / (2. * v);
+        uv / ((uv + w * w).sqrt() + w)
+    }
+
+    // Find a starting azimuth for Newton's method. Returns sig12 (or -1 if Newton's method
+    // is required), salp1, calp1, salp2, calp2 and dnm
+    fn inverse_start(&self,
+                     (sbet1, cbet1): (f64, f64),
+                     (sbet2, cbet2): (f64, f64),
+                     lam12: f64,
+                     (slam12, clam12): (f64, f64))
+                     -> (f64, f64, f64, f64, f64, f64) {
+        let mut sig12 = -1.;
+        let (mut salp2, mut calp2, mut dnm) = (0., 0., 0.);
+        let sbet12 = sbet2 * cbet1 - cbet2 * sbet1;
+        let cbet12 = cbet2 * cbet1 + sbet2 * sbet1;
+        let sbet12a = sbet2 * cbet1 + cbet2 * sbet1;
+        let shortline = cbet12 >= 0. && sbet12 < 0.5 && cbet2 * lam12 < 0.5;
+        let (mut somg12, mut comg12) = if shortline {
+            let mut sbetm2 = (sbet1 + sbet2).powi(2);
+            sbetm2 /= sbetm2 + (cbet1 + cbet2).powi(2);
+            dnm = (1. + self.ep2 * sbetm2).sqrt();
+            let omg12 = lam12 / (self.f1 * dnm);
+            omg12.sin_cos()
+        } else {
+            (slam12, clam12)
+        };
+
+        let mut salp1 = cbet2 * somg12;
+        let mut calp1 = if comg12 >= 0. {
+            sbet12 + cbet2 * sbet1 * somg12.powi(2) / (1. + comg12)
+        } else {
+            sbet12a - cbet2 * sbet1 * somg12.powi(2) / (1. - comg12)
+        };
+        let ssig12 = salp1.hypot(calp1);
+        let csig12 = sbet1 * sbet2 + cbet1 * cbet2 * comg12;
+
+        if shortline && ssig12 < self.etol2 {
+            // Really short lines
+            salp2 = cbet1 * somg12;
+            calp2 = sbet12 -
+                    cbet1 * sbet2 *
+                    (if comg12 >= 0. {
+                        somg12.powi(2) / (1. + comg12)
+                    } else {
+                        1. - comg12
+                    });
+            norm2(&mut salp2, &mut calp2);
+            sig12 = ssig12.atan2(csig12);
+        } else if self.n.abs() > 0.1 || csig12 >= 0. ||
+                  ssig12 >= 6. * self.n.abs() * f64::consts::PI * cbet1.powi(2) {
+            // The zeroth order spherical approximation is good enough
+        } else {
+            // Nearly antipodal points: scale to an astroid problem
+            let lam12x = (-slam12).atan2(-clam12);
+            let k2 = sbet1.powi(2) * self.ep2;
+            let eps = k2 / (2. * (1. + (1. + k2).sqrt()) + k2);
+            let lamscale = self.f * cbet1 * self.a3f(eps) * f64::consts::PI;
+            let betscale = lamscale * cbet1;
+            let x = lam12x / lamscale;
+            let y = sbet12a / betscale;
+            if y > -tol1() && x > -1. - xthresh() {
+                salp1 = (-x).min(1.);
+                calp1 = -(1. - salp1.powi(2)).sqrt();
+            } else {
+                let k = Geodesic::astroid(x, y);
+                let omg12a = lamscale * (-x * k / (1. + k));
+                somg12 = omg12a.sin();
+                comg12 = -omg12a.cos();
+                salp1 = cbet2 * somg12;
+                calp1 = sbet12a - cbet2 * sbet1 * somg12.powi(2) / (1. - comg12);
+            }
+        }
+        if salp1 > 0. {
+            norm2(&mut salp1, &mut calp1);
+        } else {
+            salp1 = 1.;
+            calp1 = 0.;
+        }
+        (sig12, salp1, calp1, salp2, calp2, dnm)
+    }
+
+    // The longitude difference for a given starting azimuth, less the target lam120,
+    // and (if diffp) its derivative with respect to the azimuth. Each point is given as
+    // (sin β, cos β, dn), where β is the reduced latitude
+    fn lambda12(&self,
+                (sbet1, cbet1, dn1): (f64, f64, f64),
+                (sbet2, cbet2, dn2): (f64, f64, f64),
+                (salp1, calp1): (f64, f64),
+                (slam120, clam120): (f64, f64),
+                diffp: bool)
+                -> ([f64; 9], f64, f64) {
+        let calp1 = if sbet1 == 0. && calp1 == 0. {
+            -tiny()
+        } else {
+            calp1
+        };
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+
+        let mut ssig1 = sbet1;
+        let somg1 = salp0 * sbet1;
+        let mut csig1 = calp1 * cbet1;
+        let comg1 = csig1;
+        norm2(&mut ssig1, &mut csig1);
+
+        let salp2 = if cbet2 != cbet1 { salp0 / cbet2 } else { salp1 };
+        let calp2 = if cbet2 != cbet1 || sbet2.abs() != -sbet1 {
+            ((calp1 * cbet1).powi(2) +
+             if cbet1 < -sbet1 {
+                (cbet2 - cbet1) * (cbet1 + cbet2)
+            } else {
+                (sbet1 - sbet2) * (sbet1 + sbet2)
+            })
+                .sqrt() / cbet2
+        } else {
+            calp1.abs()
+        };
+        let mut ssig2 = sbet2;
+        let somg2 = salp0 * sbet2;
+        let mut csig2 = calp2 * cbet2;
+        let comg2 = csig2;
+        norm2(&mut ssig2, &mut csig2);
+
+        let sig12 = ((csig1 * ssig2 - ssig1 * csig2).max(0.) + 0.)
+            .atan2(csig1 * csig2 + ssig1 * ssig2);
+        let somg12 = (comg1 * somg2 - somg1 * comg2).max(0.) + 0.;
+        let comg12 = comg1 * comg2 + somg1 * somg2;
+        let eta = (somg12 * clam120 - comg12 * slam120)
+            .atan2(comg12 * clam120 + somg12 * slam120);
+        let k2 = calp0.powi(2) * self.ep2;
+        let eps = k2 / (2. * (1. + (1. + k2).sqrt()) + k2);
+        let mut c3a = [0.; ORDER];
+        self.c3f(eps, &mut c3a);
+        let b312 = sin_cos_series(true, ssig2, csig2, &c3a, ORDER - 1) -
+                   sin_cos_series(true, ssig1, csig1, &c3a, ORDER - 1);
+        let domg12 = -self.f * self.a3f(eps) * salp0 * (sig12 + b312);
+        let lam12 = eta + domg12;
+
+        let mut dlam12 = 0.;
+        if diffp {
+            if calp2 == 0. {
+                dlam12 = -2. * self.f1 * dn1 / sbet1;
+            } else {
+                let lengths = self.lengths(eps, sig12, (ssig1, csig1, dn1), (ssig2, csig2, dn2));
+                dlam12 = lengths.m12b * self.f1 / (calp2 * cbet2);
+            }
+        }
+        ([salp2, calp2, sig12, ssig1, csig1, ssig2, csig2, eps, domg12], lam12, dlam12)
+    }
+
+    // Returns the distance, the forward azimuths at each point, and the area between the
+    // geodesic and the equator, counter-clockwise positive
+    fn inverse(&self, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64, f64, f64) {
+        let tiny = tiny();
+        let lon12 = ang_round(ang_normalise(lon2 - lon1));
+        let mut lonsign = if lon12 < 0. { -1. } else { 1. };
+        let lon12 = lon12 * lonsign;
+        let lam12 = lon12.to_radians();
+        let (slam12, clam12) = sincosd(lon12);
+        // The supplementary longitude difference
+        let lon12s = 180. - lon12;
+
+        let mut lat1 = ang_round(lat1);
+        let mut lat2 = ang_round(lat2);
+        // Make lat1 the point furthest from the equator, and make it negative
+        let swapp = if lat1.abs() < lat2.abs() { -1. } else { 1. };
+        if swapp < 0. {
+            lonsign *= -1.;
+            ::std::mem::swap(&mut lat1, &mut lat2);
+        }
+        let latsign = if lat1 < 0. { 1. } else { -1. };
+        lat1 *= latsign;
+        lat2 *= latsign;
+
+        let (mut sbet1, mut cbet1) = sincosd(lat1);
+        sbet1 *= self.f1;
+        norm2(&mut sbet1, &mut cbet1);
+        cbet1 = cbet1.max(tiny);
+        let (mut sbet2, mut cbet2) = sincosd(lat2);
+        sbet2 *= self.f1;
+        norm2(&mut sbet2, &mut cbet2);
+        cbet2 = cbet2.max(tiny);
+
+        if cbet1 < -sbet1 {
+            if cbet2 == cbet1 {
+                sbet2 = if sbet2 < 0. { sbet1 } else { -sbet1 };
+            }
+        } else if sbet2.abs() == -sbet1 {
+            cbet2 = cbet1;
+        }
+
+        let dn1 = (1. + self.ep2 * sbet1.powi(2)).sqrt();
+        let dn2 = (1. + self.ep2 * sbet2.powi(2)).sqrt();
+
+        let (mut salp1, mut calp1, mut salp2, mut calp2) = (0., 0., 0., 0.);
+        let mut s12x = 0.;
+        // The longitude difference on the auxiliary sphere, if it isn't lam12
+        let mut omg12 = None;
+        let mut meridian = lat1 == -90. || slam12 == 0.;
+
+        if meridian {
+            // The geodesic is a meridian, or runs through a pole
+            calp1 = clam12;
+            salp1 = slam12;
+            calp2 = 1.;
+            salp2 = 0.;
+            let ssig1 = sbet1;
+            let csig1 = calp1 * cbet1;
+            let ssig2 = sbet2;
+            let csig2 = calp2 * cbet2;
+            let sig12 = ((csig1 * ssig2 - ssig1 * csig2).max(0.) + 0.)
+                .atan2(csig1 * csig2 + ssig1 * ssig2);
+            let lengths = self.lengths(self.n, sig12, (ssig1, csig1, dn1), (ssig2, csig2, dn2));
+            let (s12b, m12b) = (lengths.s12b, lengths.m12b);
+            if sig12 < 1. || m12b >= 0. {
+                // Points which are very close together have zero distance
+                if sig12 < 3. * tiny || (sig12 < TOL0 && (s12b < 0. || m12b < 0.)) {
+                    s12x = 0.;
+                } else {
+                    s12x = s12b * self.b;
+                }
+            } else {
+                meridian = false;
+            }
+        }
+
+        if !meridian && sbet1 == 0. && (self.f <= 0. || lon12s >= self.f * 180.) {
+            // The geodesic runs along the equator
+            calp1 = 0.;
+            calp2 = 0.;
+            salp1 = 1.;
+            salp2 = 1.;
+            s12x = self.a * lam12;
+            omg12 = Some((lam12 / self.f1).sin_cos());
+        } else if !meridian {
+            let (sig12, s1, c1, s2, c2, dnm) =
+                self.inverse_start((sbet1, cbet1), (sbet2, cbet2), lam12, (slam12, clam12));
+            salp1 = s1;
+            calp1 = c1;
+            if sig12 >= 0. {
+                // Short lines: the starting point is the solution
+                salp2 = s2;
+                calp2 = c2;
+                s12x = sig12 * self.b * dnm;
+                omg12 = Some((lam12 / (self.f1 * dnm)).sin_cos());
+            } else {
+                // Newton's method, falling back to bisection
+                let mut numit = 0;
+                let (mut salp1a, mut calp1a, mut salp1b, mut calp1b) = (tiny, 1., tiny, -1.);
+                let mut tripn = false;
+                let mut tripb = false;
+                let mut state;
+                loop {
+                    let (s, v, dv) = self.lambda12((sbet1, cbet1, dn1),
+                                                   (sbet2, cbet2, dn2),
+                                                   (salp1, calp1),
+                                                   (slam12, clam12),
+                                                   numit < MAXIT1);
+                    state = s;
+                    if tripb || !(v.abs() >= if tripn { 8. } else { 1. } * TOL0) ||
+                       numit == MAXIT2 {
+                        break;
+                    }
+                    if v > 0. && (numit > MAXIT1 || calp1 / salp1 > calp1b / salp1b) {
+                        salp1b = salp1;
+                        calp1b = calp1;
+                    } else if v < 0. && (numit > MAXIT1 || calp1 / salp1 < calp1a / salp1a) {
+                        salp1a = salp1;
+                        calp1a = calp1;
+                    }
+                    numit += 1;
+                    if numit <= MAXIT1 && dv > 0. {
+                        let dalp1 = -v / dv;
+                        if dalp1.abs() < f64::consts::PI {
+                            let (sdalp1, cdalp1) = dalp1.sin_cos();
+                            let nsalp1 = salp1 * cdalp1 + calp1 * sdalp1;
+                            if nsalp1 > 0. {
+                                calp1 = calp1 * cdalp1 - salp1 * sdalp1;
+                                salp1 = nsalp1;
+                                norm2(&mut salp1, &mut calp1);
+                                tripn = v.abs() <= 16. * TOL0;
+                                continue;
+                            }
+                        }
+                    }
+                    salp1 = (salp1a + salp1b) / 2.;
+                    calp1 = (calp1a + calp1b) / 2.;
+                    norm2(&mut salp1, &mut calp1);
+                    tripn = false;
+                    tripb = (salp1a - salp1).abs() + (calp1a - calp1) < tolb() ||
+                            (salp1 - salp1b).abs() + (calp1 - calp1b) < tolb();
+                }
+                let [s2, c2, sig12, ssig1, csig1, ssig2, csig2, eps, domg12] = state;
+                salp2 = s2;
+                calp2 = c2;
+                let lengths = self.lengths(eps, sig12, (ssig1, csig1, dn1), (ssig2, csig2, dn2));
+                s12x = lengths.s12b * self.b;
+                // omg12 = lam12 - domg12
+                let (sdomg12, cdomg12) = domg12.sin_cos();
+                omg12 = Some((slam12 * cdomg12 - clam12 * sdomg12,
+                              clam12 * cdomg12 + slam12 * sdomg12));
+            }
+        }
+
+        // The area between the geodesic and the equator
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+        let mut area = if calp0 != 0. && salp0 != 0. {
+            let (mut ssig1, mut csig1) = (sbet1, calp1 * cbet1);
+            let (mut ssig2, mut csig2) = (sbet2, calp2 * cbet2);
+            let k2 = calp0.powi(2) * self.ep2;
+            let eps = k2 / (2. * (1. + (1. + k2).sqrt()) + k2);
+            let a4 = self.a.powi(2) * calp0 * salp0 * self.e2;
+            norm2(&mut ssig1, &mut csig1);
+            norm2(&mut ssig2, &mut csig2);
+            let mut c4a = [0.; ORDER];
+            self.c4f(eps, &mut c4a);
+            a4 *
+            (sin_cos_series(false, ssig2, csig2, &c4a, ORDER) -
+             sin_cos_series(false, ssig1, csig1, &c4a, ORDER))
+        } else {
+            0.
+        };
+        let alp12 = match omg12 {
+            Some((somg12, comg12)) if !meridian && comg12 > -0.7071 && sbet2 - sbet1 < 1.75 => {
+                // tan(Γ/2) = tan(ω12/2) (tan(β1/2) + tan(β2/2)) / (1 + tan(β1/2) tan(β2/2))
+                let (domg12, dbet1, dbet2) = (1. + comg12, 1. + cbet1, 1. + cbet2);
+                2. *
+                (somg12 * (sbet1 * dbet2 + sbet2 * dbet1))
+                    .atan2(domg12 * (sbet1 * sbet2 + dbet1 * dbet2))
+            }
+            _ => {
+                let mut salp12 = salp2 * calp1 - calp2 * salp1;
+                let mut calp12 = calp2 * calp1 + salp2 * salp1;
+                if salp12 == 0. && calp12 < 0. {
+                    salp12 = tiny * calp1;
+                    calp12 = -1.;
+                }
+                salp12.atan2(calp12)
+            }
+        };
+        area += self.c2 * alp12;
+        area *= swapp * lonsign * latsign;
+
+        if swapp < 0. {
+            ::std::mem::swap(&mut salp1, &mut salp2);
+            ::std::mem::swap(&mut calp1, &mut calp2);
+        }
+        salp1 *= swapp * lonsign;
+        calp1 *= swapp * latsign;
+        salp2 *= swapp * lonsign;
+        calp2 *= swapp * latsign;
+        (s12x + 0., atan2d(salp1, calp1), atan2d(salp2, calp2), area + 0.)
+    }
+
+    // Returns the latitude and longitude of the end point, and the forward azimuth there
+    fn direct(&self, lat1: f64, lon1: f64, azi1: f64, s12: f64) -> (f64, f64, f64) {
+        let tiny = tiny();
+        let (salp1, calp1) = sincosd(ang_round(azi1));
+        let (mut sbet1, mut cbet1) = sincosd(ang_round(lat1));
+        sbet1 *= self.f1;
+        norm2(&mut sbet1, &mut cbet1);
+        cbet1 = cbet1.max(tiny);
+
+        let salp0 = salp1 * cbet1;
+        let calp0 = calp1.hypot(salp1 * sbet1);
+        let mut ssig1 = sbet1;
+        let somg1 = salp0 * sbet1;
+        let mut csig1 = if sbet1 != 0. || calp1 != 0. {
+            cbet1 * calp1
+        } else {
+            1.
+        };
+        let comg1 = csig1;
+        norm2(&mut ssig1, &mut csig1);
+
+        let k2 = calp0.powi(2) * self.ep2;
+        let eps = k2 / (2. * (1. + (1. + k2).sqrt()) + k2);
+        let a1m1 = a1m1f(eps);
+        let mut c1a = [0.; ORDER + 1];
+        c1f(eps, &mut c1a);
+        let mut c1pa = [0.; ORDER + 1];
+        c1pf(eps, &mut c1pa);
+        let b11 = sin_cos_series(true, ssig1, csig1, &c1a, ORDER);
+        let (s, c) = b11.sin_cos();
+        let stau1 = ssig1 * c + csig1 * s;
+        let ctau1 = csig1 * c - ssig1 * s;
+        let a3c = -self.f * salp0 * self.a3f(eps);
+        let mut c3a = [0.; ORDER];
+        self.c3f(eps, &mut c3a);
+        let b31 = sin_cos_series(true, ssig1, csig1, &c3a, ORDER - 1);
+
+        let tau12 = s12 / (self.b * (1. + a1m1));
+        let (s, c) = tau12.sin_cos();
+        let b12 = -sin_cos_series(true,
+                                  stau1 * c + ctau1 * s,
+                                  ctau1 * c - stau1 * s,
+                                  &c1pa,
+                                  ORDER);
+        let sig12 = tau12 - (b12 - b11);
+        let (ssig12, csig12) = sig12.sin_cos();
+
+        let ssig2 = ssig1 * csig12 + csig1 * ssig12;
+        let mut csig2 = csig1 * csig12 - ssig1 * ssig12;
+        let sbet2 = calp0 * ssig2;
+        let mut cbet2 = salp0.hypot(calp0 * csig2);
+        if cbet2 == 0. {
+            cbet2 = tiny;
+            csig2 = tiny;
+        }
+        let salp2 = salp0;
+        let calp2 = calp0 * csig2;
+
+        let somg2 = salp0 * ssig2;
+        let comg2 = csig2;
+        let omg12 = (somg2 * comg1 - comg2 * somg1).atan2(comg2 * comg1 + somg2 * somg1);
+        let lam12 = omg12 +
+                    a3c * (sig12 + (sin_cos_series(true, ssig2, csig2, &c3a, ORDER - 1) - b31));
+        let lon2 = ang_normalise(ang_normalise(lon1) + ang_normalise(lam12.to_degrees()));
+        (atan2d(sbet2, self.f1 * cbet2), lon2, atan2d(salp2, calp2))
+    }
+}
+
+/// Solve the inverse geodesic problem: find the shortest distance (m) between two points
+/// on `ellipsoid`, the azimuth at the first point, and the back azimuth at the second
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{geodesic_inverse, Ellipsoid};
+/// // Land's End to John o' Groats
+/// let (distance, azimuth, back_azimuth) =
+///     geodesic_inverse(&-5.71475, &50.06632, &-3.07009, &58.64402, &Ellipsoid::GRS80)
+///         .unwrap();
+/// ```
+pub fn geodesic_inverse(lon1: &f64,
+                        lat1: &f64,
+                        lon2: &f64,
+                        lat2: &f64,
+                        ellipsoid: &Ellipsoid)
+                        -> Result<(f64, f64, f64), ConversionError> {
+    solve_inverse(&Geodesic::new(ellipsoid), lon1, lat1, lon2, lat2)
+}
+
+// geodesic_inverse, using a Geodesic which the threaded functions build once per batch
+fn solve_inverse(geodesic: &Geodesic,
+                 lon1: &f64,
+                 lat1: &f64,
+                 lon2: &f64,
+                 lat2: &f64)
+                 -> Result<(f64, f64, f64), ConversionError> {
+    let lon1 = check(*lon1, (-180., 180.), Axis::Longitude)?;
+    let lat1 = check(*lat1, (-90., 90.), Axis::Latitude)?;
+    let lon2 = check(*lon2, (-180., 180.), Axis::Longitude)?;
+    let lat2 = check(*lat2, (-90., 90.), Axis::Latitude)?;
+    let (distance, azi1, azi2, _) = geodesic.inverse(lat1, lon1, lat2, lon2);
+    Ok((distance, normalise_bearing(azi1), normalise_bearing(azi2 + 180.)))
+}
+
+/// Solve the direct geodesic problem: find the point reached by travelling `distance` (m)
+/// from a point on `ellipsoid` with the given initial azimuth, and the back azimuth there
+///
+/// Returns the longitude and latitude of the point, and the back azimuth
+pub fn geodesic_direct(lon1: &f64,
+                       lat1: &f64,
+                       azimuth: &f64,
+                       distance: &f64,
+                       ellipsoid: &Ellipsoid)
+                       -> Result<(f64, f64, f64), ConversionError> {
+    solve_direct(&Geodesic::new(ellipsoid), lon1, lat1, azimuth, distance)
+}
+
+// geodesic_direct, using a Geodesic which the threaded functions build once per batch
+fn solve_direct(geodesic: &Geodesic,
+                lon1: &f64,
+                lat1: &f64,
+                azimuth: &f64,
+                distance: &f64)
+                -> Result<(f64, f64, f64), ConversionError> {
+    let lon1 = check(*lon1, (-180., 180.), Axis::Longitude)?;
+    let lat1 = check(*lat1, (-90., 90.), Axis::Latitude)?;
+    let azimuth = check(*azimuth, (-360., 360.), Axis::Bearing)?;
+    let distance = check(*distance, (f64::MIN, f64::MAX), Axis::Distance)?;
+    let (lat2, lon2, azi2) = geodesic.direct(lat1, lon1, azimuth, distance);
+    Ok((lon2, lat2, normalise_bearing(azi2 + 180.)))
+}
+
+/// Calculate the area (m²) and perimeter (m) of a polygon ring on `ellipsoid`, whose vertices
+/// are joined by geodesics
+///
+/// The ring may be given in either direction, and a closing vertex which repeats the first is
+/// ignored. The smaller of the two areas the ring divides the ellipsoid into is returned
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{geodesic_polygon_area, Ellipsoid};
+/// let lons = [-0.1, 0.0, 0.0, -0.1];
+/// let lats = [51.5, 51.5, 51.6, 51.6];
+/// let (area, perimeter) = geodesic_polygon_area(&lons, &lats, &Ellipsoid::GRS80).unwrap();
+/// ```
+pub fn geodesic_polygon_area(longitudes: &[f64],
+                             latitudes: &[f64],
+                             ellipsoid: &Ellipsoid)
+                             -> Result<(f64, f64), ConversionError> {
+    let mut n = longitudes.len().min(latitudes.len());
+    if n > 1 && longitudes[0] == longitudes[n - 1] && latitudes[0] == latitudes[n - 1] {
+        n -= 1;
+    }
+    for (lon, lat) in longitudes[..n].iter().zip(&latitudes[..n]) {
+        check(*lon, (-180., 180.), Axis::Longitude)?;
+        check(*lat, (-90., 90.), Axis::Latitude)?;
+    }
+    let geodesic = Geodesic::new(ellipsoid);
+    let mut area = 0.;
+    let mut perimeter = 0.;
+    let mut crossings = 0;
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let (s12, _, _, s) =
+            geodesic.inverse(latitudes[i], longitudes[i], latitudes[j], longitudes[j]);
+        perimeter += s12;
+        area += s;
+        crossings += transit(longitudes[i], longitudes[j]);
+    }
+    // Rings which encircle a pole cross the prime meridian an odd number of times
+    let total = 4. * f64::consts::PI * geodesic.c2;
+    if crossings % 2 != 0 {
+        area += if area < 0. { 1. } else { -1. } * total / 2.;
+    }
+    if area > total / 2. {
+        area -= total;
+    } else if area <= -total / 2. {
+        area += total;
+    }
+    Ok((area.abs(), perimeter))
+}
+
+/// A threaded wrapper for [`lonlat_bng::geodesic_inverse`](fn.geodesic_inverse.html),
+/// which solves the problem for each pair of points in turn
+///
+/// The first longitudes are replaced by distances, the first latitudes by azimuths, and the
+/// second longitudes by back azimuths. Pairs which can't be solved are set to `NAN`
+///
+/// # Panics
+///
+/// Panics if the slices differ in length
+pub fn geodesic_inverse_threaded_vec<'a>(lons1: &'a mut [f64],
+                                         lats1: &'a mut [f64],
+                                         lons2: &'a mut [f64],
+                                         lats2: &[f64],
+                                         ellipsoid: &Ellipsoid)
+                                         -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    assert!(lons1.len() == lats1.len() && lons1.len() == lons2.len() &&
+            lons1.len() == lats2.len(),
+            "coordinate slices must have the same length");
+    let geodesic = Geodesic::new(ellipsoid);
+    lons1.par_iter_mut()
+        .zip(lats1.par_iter_mut())
+        .zip(lons2.par_iter_mut().zip(lats2.par_iter()))
+        .for_each(|((lon1, lat1), (lon2, lat2))| {
+            let res = solve_inverse(&geodesic, lon1, lat1, lon2, lat2)
+                .unwrap_or((f64::NAN, f64::NAN, f64::NAN));
+            *lon1 = res.0;
+            *lat1 = res.1;
+            *lon2 = res.2;
+        });
+    (lons1, lats1, lons2)
+}
+
+/// Solve the inverse geodesic problem for each segment of a path, given as longitudes and
+/// latitudes, using multiple threads
+///
+/// Returns the distance, azimuth and back azimuth for each of the `n - 1` segments
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{geodesic_path_threaded_vec, Ellipsoid};
+/// let lons = [-0.1276, -1.8904, -2.2426];
+/// let lats = [51.5072, 52.4862, 53.4808];
+/// let segments = geodesic_path_threaded_vec(&lons, &lats, &Ellipsoid::GRS80);
+/// let length: f64 = segments.iter().map(|segment| segment.0).sum();
+/// ```
+pub fn geodesic_path_threaded_vec(longitudes: &[f64],
+                                  latitudes: &[f64],
+                                  ellipsoid: &Ellipsoid)
+                                  -> Vec<(f64, f64, f64)> {
+    let n = longitudes.len().min(latitudes.len());
+    if n < 2 {
+        return vec![];
+    }
+    let geodesic = Geodesic::new(ellipsoid);
+    longitudes[..n - 1]
+        .par_iter()
+        .zip(latitudes[..n - 1].par_iter())
+        .zip(longitudes[1..n].par_iter().zip(latitudes[1..n].par_iter()))
+        .map(|((lon1, lat1), (lon2, lat2))| {
+            solve_inverse(&geodesic, lon1, lat1, lon2, lat2)
+                .unwrap_or((f64::NAN, f64::NAN, f64::NAN))
+        })
+        .collect()
+}
+
+/// A threaded wrapper for [`lonlat_bng::geodesic_direct`](fn.geodesic_direct.html)
+///
+/// The longitudes and latitudes are replaced by those of the points reached, and the
+/// azimuths by the back azimuths there. Points which can't be reached are set to `NAN`
+///
+/// # Panics
+///
+/// Panics if the slices differ in length
+pub fn geodesic_direct_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                        latitudes: &'a mut [f64],
+                                        azimuths: &'a mut [f64],
+                                        distances: &[f64],
+                                        ellipsoid: &Ellipsoid)
+                                        -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    assert!(longitudes.len() == latitudes.len() && longitudes.len() == azimuths.len() &&
+            longitudes.len() == distances.len(),
+            "coordinate slices must have the same length");
+    let geodesic = Geodesic::new(ellipsoid);
+    longitudes.par_iter_mut()
+        .zip(latitudes.par_iter_mut())
+        .zip(azimuths.par_iter_mut().zip(distances.par_iter()))
+        .for_each(|((lon, lat), (azimuth, distance))| {
+            let res = solve_direct(&geodesic, lon, lat, azimuth, distance)
+                .unwrap_or((f64::NAN, f64::NAN, f64::NAN));
+            *lon = res.0;
+            *lat = res.1;
+            *azimuth = res.2;
+        });
+    (longitudes, latitudes, azimuths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The GeographicLib examples use WGS84
+    const WGS84: Ellipsoid = Ellipsoid::WGS84;
+
+    #[test]
+    fn test_inverse() {
+        // JFK to LHR
+        let (s12, azi1, back) = geodesic_inverse(&-73.8, &40.6, &-0.5, &51.6, &WGS84).unwrap();
+        assert!((s12 - 5551759.400319).abs() < 0.000001);
+        assert!((azi1 - 51.198882845579).abs() < 1e-9);
+        assert!((back - (107.821776735514 + 180.)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_direct() {
+        let (lon2, lat2, back) =
+            geodesic_direct(&-73.77888889, &40.63972222, &53.5, &5850e3, &WGS84).unwrap();
+        assert!((lat2 - 49.01467).abs() < 0.00001);
+        assert!((lon2 - 2.56106).abs() < 0.00001);
+        assert!((back - (111.62947 + 180.)).abs() < 0.00001);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for ellipsoid in &[Ellipsoid::GRS80, Ellipsoid::AIRY_1830] {
+            let (s12, azi1, back) =
+                geodesic_inverse(&-5.71475, &50.06632, &-3.07009, &58.64402, ellipsoid)
+                    .unwrap();
+            let (lon2, lat2, back2) = geodesic_direct(&-5.71475, &50.06632, &azi1, &s12, ellipsoid)
+                .unwrap();
+            assert!((lon2 - -3.07009).abs() < 1e-12);
+            assert!((lat2 - 58.64402).abs() < 1e-12);
+            assert!((back - back2).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_special_cases() {
+        // Along a meridian, and along the equator
+        let (s12, azi1, back) = geodesic_inverse(&-2., &50., &-2., &51., &Ellipsoid::GRS80)
+            .unwrap();
+        assert!((s12 - 111238.681).abs() < 0.001);
+        assert_eq!((0., 180.), (azi1, back));
+        let (s12, azi1, _) = geodesic_inverse(&0., &0., &1., &0., &Ellipsoid::GRS80).unwrap();
+        assert!((s12 - 111319.491).abs() < 0.001);
+        assert_eq!(90., azi1);
+        // Coincident and nearly antipodal points
+        assert_eq!(0., geodesic_inverse(&1., &52., &1., &52., &WGS84).unwrap().0);
+        let (s12, _, _) = geodesic_inverse(&0., &-30.12345, &179.8, &30.12345, &WGS84).unwrap();
+        assert!(s12 > 19900000. && s12 < 20010000.);
+    }
+
+    #[test]
+    fn test_polygon_area() {
+        // An octant of the ellipsoid, bounded by two meridians and the equator
+        let (area, perimeter) = geodesic_polygon_area(&[0., 90., 0.], &[0., 0., 90.], &WGS84)
+            .unwrap();
+        assert!((area - 510065621724088.5 / 8.).abs() < 1.);
+        assert!((perimeter - (2. * 10001965.729 + 10018754.171)).abs() < 0.01);
+        // Direction and a closing vertex make no difference
+        let (reversed, _) =
+            geodesic_polygon_area(&[0., 0., 90., 0.], &[0., 90., 0., 0.], &WGS84).unwrap();
+        assert!((reversed - area).abs() < 0.01);
+        // A ring around the north pole
+        let (cap, _) = geodesic_polygon_area(&[0., 90., 180., -90.], &[0., 0., 0., 0.], &WGS84)
+            .unwrap();
+        assert!((cap - 510065621724088.5 / 2.).abs() < 1.);
+        let (empty, _) = geodesic_polygon_area(&[0., 1.], &[52., 52.], &WGS84).unwrap();
+        assert_eq!(0., empty);
+    }
+
+    #[test]
+    fn test_path() {
+        let lons = [-0.1276, -1.8904, -2.2426];
+        let lats = [51.5072, 52.4862, 53.4808];
+        let segments = geodesic_path_threaded_vec(&lons, &lats, &Ellipsoid::GRS80);
+        assert_eq!(2, segments.len());
+        assert_eq!(geodesic_inverse(&lons[1], &lats[1], &lons[2], &lats[2], &Ellipsoid::GRS80)
+                       .unwrap(),
+                   segments[1]);
+    }
+
+    #[test]
+    fn test_threaded_inverse() {
+        let lons1: &mut [f64] = &mut [-73.8, 0.];
+        let lats1: &mut [f64] = &mut [40.6, 91.];
+        let lons2: &mut [f64] = &mut [-0.5, 0.];
+        let lats2 = [51.6, 0.];
+        let (distances, azimuths, back) =
+            geodesic_inverse_threaded_vec(lons1, lats1, lons2, &lats2, &WGS84);
+        assert_eq!(geodesic_inverse(&-73.8, &40.6, &-0.5, &51.6, &WGS84).unwrap(),
+                   (distances[0], azimuths[0], back[0]));
+        assert!(distances[1].is_nan());
+    }
+
+    #[test]
+    fn test_threaded_direct() {
+        let lons: &mut [f64] = &mut [-73.77888889, 0.];
+        let lats: &mut [f64] = &mut [40.63972222, 0.];
+        let azimuths: &mut [f64] = &mut [53.5, 361.];
+        let distances = [5850e3, 1000.];
+        let (lons2, lats2, back) =
+            geodesic_direct_threaded_vec(lons, lats, azimuths, &distances, &WGS84);
+        assert_eq!(geodesic_direct(&-73.77888889, &40.63972222, &53.5, &5850e3, &WGS84).unwrap(),
+                   (lons2[0], lats2[0], back[0]));
+        assert!(lons2[1].is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_threaded_inverse_short_slice() {
+        let lons1: &mut [f64] = &mut [0., 1.];
+        let lats1: &mut [f64] = &mut [0., 1.];
+        let lons2: &mut [f64] = &mut [1., 2.];
+        geodesic_inverse_threaded_vec(lons1, lats1, lons2, &[1.], &WGS84);
+    }
+}
@@ -0,0 +1,245 @@
+//! This module provides alphanumeric grid references
+//!
+//! A grid reference replaces the leading digits of an easting and northing with the letter(s)
+//! of the 100 km square which contains the point, e.g. `TQ 16276 73141` on the National Grid,
+//! or `O 1590 3467` on the Irish Grid.
+//! Coordinates are truncated, not rounded, so a grid reference always identifies the
+//! square which contains the point.
+use rayon::prelude::*;
+
+use conversions::{MAX_EASTING, MAX_NORTHING};
+use errors::{Axis, ConversionError};
+use irish::{MAX_IRISH_GRID_EASTING, MAX_IRISH_GRID_NORTHING};
+use utils::check;
+
+// 100 km squares, from the north-west corner, in rows of five. The National Grid uses the
+// same letters for its 500 km squares
+const IRISH_LETTERS: &'static [u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+const SQUARE_SIZE: f64 = 100000.;
+
+// Check that a grid reference has an even number of digits, from 2 (10 km) to 10 (1 m)
+fn check_digits(digits: usize) -> Result<usize, ConversionError> {
+    if digits >= 2 && digits <= 10 && digits % 2 == 0 {
+        Ok(digits / 2)
+    } else {
+        Err(ConversionError::InvalidGridReference)
+    }
+}
+
+// Check that a coordinate lies in [0, max): the edge at `max` belongs to a square outside
+// the grid
+fn check_within_grid(value: f64, max: f64, axis: Axis) -> Result<f64, ConversionError> {
+    match check(value, (0., max), axis)? {
+        value if value < max => Ok(value),
+        value => {
+            Err(ConversionError::OutOfBounds {
+                axis: axis,
+                value: value,
+                min: 0.,
+                max: max,
+            })
+        }
+    }
+}
+
+// Write the position of a point within its 100 km square using `places` digits per axis
+fn format_offsets(eastings: f64, northings: f64, places: usize) -> (String, String) {
+    let scale = 10f64.powi(5 - places as i32);
+    let e = ((eastings % SQUARE_SIZE) / scale).floor() as u32;
+    let n = ((northings % SQUARE_SIZE) / scale).floor() as u32;
+    (format!("{:0width$}", e, width = places), format!("{:0width$}", n, width = places))
+}
+
+// Parse the digits following the square letter(s) into an offset within the 100 km square
+fn parse_offsets(digits: &str) -> Result<(f64, f64), ConversionError> {
+    let digits: String = digits.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() > 10 || digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_digit(10)) {
+        return Err(ConversionError::InvalidGridReference);
+    }
+    if digits.is_empty() {
+        return Ok((0., 0.));
+    }
+    let places = digits.len() / 2;
+    let scale = 10f64.powi(5 - places as i32);
+    let (e, n) = digits.split_at(places);
+    let parse = |s: &str| {
+        s.parse::<f64>().map(|v| v * scale).map_err(|_| ConversionError::InvalidGridReference)
+    };
+    Ok((parse(e)?, parse(n)?))
+}
+
+/// Write Irish Grid (EPSG:29903) coordinates as an alphanumeric grid reference with `digits`
+/// digits: an even number from 2 (10 km precision) to 10 (1 m precision)
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::irish_grid_reference;
+/// assert_eq!("O 1590 3467", irish_grid_reference(&315904., &234671., 8).unwrap());
+/// ```
+pub fn irish_grid_reference(eastings: &f64,
+                            northings: &f64,
+                            digits: usize)
+                            -> Result<String, ConversionError> {
+    let places = check_digits(digits)?;
+    let e = check_within_grid(*eastings, MAX_IRISH_GRID_EASTING, Axis::Easting)?;
+    let n = check_within_grid(*northings, MAX_IRISH_GRID_NORTHING, Axis::Northing)?;
+    let column = (e / SQUARE_SIZE).floor() as usize;
+    let row = 4 - (n / SQUARE_SIZE).floor() as usize;
+    let letter = IRISH_LETTERS[row * 5 + column] as char;
+    let (e_digits, n_digits) = format_offsets(e, n, places);
+    Ok(format!("{} {} {}", letter, e_digits, n_digits))
+}
+
+/// Write OSGB36 (EPSG:27700) coordinates as an alphanumeric National Grid reference with
+/// `digits` digits: an even number from 2 (10 km precision), through 4 (1 km), 6 (100 m) and
+/// 8 (10 m), to 10 (1 m precision)
+///
+/// If `spaces` is `false`, the letters and digits are written without separating spaces.
+/// Points in the northernmost row of squares (HL to HP, and JL to JM) are supported up to
+/// a Northing of 1250000 m
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::os_grid_reference;
+/// assert_eq!("TQ 16276 73141", os_grid_reference(&516276., &173141., 10, true).unwrap());
+/// assert_eq!("TQ162731", os_grid_reference(&516276., &173141., 6, false).unwrap());
+/// ```
+pub fn os_grid_reference(eastings: &f64,
+                         northings: &f64,
+                         digits: usize,
+                         spaces: bool)
+                         -> Result<String, ConversionError> {
+    let places = check_digits(digits)?;
+    let e = check_within_grid(*eastings, MAX_EASTING, Axis::Easting)?;
+    let n = check(*northings, (0., MAX_NORTHING), Axis::Northing)?;
+    let column = (e / SQUARE_SIZE).floor() as usize;
+    // Rows of 100 km squares, counted from the north of the 2500 km square lettered A
+    let row = 19 - (n / SQUARE_SIZE).floor() as usize;
+    // The grid's false origin lies at the south-west corner of square SV
+    let first = IRISH_LETTERS[(row / 5) * 5 + (column + 10) / 5] as char;
+    let second = IRISH_LETTERS[(row % 5) * 5 + column % 5] as char;
+    let (e_digits, n_digits) = format_offsets(e, n, places);
+    if spaces {
+        Ok(format!("{}{} {} {}", first, second, e_digits, n_digits))
+    } else {
+        Ok(format!("{}{}{}{}", first, second, e_digits, n_digits))
+    }
+}
+
+/// A threaded wrapper for [`lonlat_bng::os_grid_reference`](fn.os_grid_reference.html)
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::os_grid_reference_threaded_vec;
+/// let refs = os_grid_reference_threaded_vec(&[516276., 700000.], &[173141., 0.], 8, true);
+/// assert_eq!(Ok("TQ 1627 7314".to_string()), refs[0]);
+/// assert!(refs[1].is_err());
+/// ```
+pub fn os_grid_reference_threaded_vec(eastings: &[f64],
+                                      northings: &[f64],
+                                      digits: usize,
+                                      spaces: bool)
+                                      -> Vec<Result<String, ConversionError>> {
+    eastings.par_iter()
+        .zip(northings.par_iter())
+        .map(|(e, n)| os_grid_reference(e, n, digits, spaces))
+        .collect()
+}
+
+/// Parse an Irish Grid alphanumeric grid reference, such as `O 1590 3467` or `o15903467`,
+/// returning the Irish Grid (EPSG:29903) coordinates of its south-west corner
+pub fn parse_irish_grid_reference(grid_ref: &str) -> Result<(f64, f64), ConversionError> {
+    let grid_ref = grid_ref.trim();
+    let letter = grid_ref.chars()
+        .next()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase() as u8)
+        .ok_or(ConversionError::InvalidGridReference)?;
+    let index = IRISH_LETTERS.iter()
+        .position(|&l| l == letter)
+        .ok_or(ConversionError::InvalidGridReference)?;
+    let (e, n) = parse_offsets(&grid_ref[1..])?;
+    Ok(((index % 5) as f64 * SQUARE_SIZE + e, (4 - index / 5) as f64 * SQUARE_SIZE + n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_irish_grid_reference() {
+        assert_eq!("O 1590 3467", irish_grid_reference(&315904., &234671., 8).unwrap());
+        assert_eq!("O 15904 34671", irish_grid_reference(&315904., &234671., 10).unwrap());
+        // Truncated, not rounded
+        assert_eq!("O 1 3", irish_grid_reference(&319999., &239999., 2).unwrap());
+        assert_eq!("V 00 00", irish_grid_reference(&0., &0., 4).unwrap());
+        assert_eq!("E 999 999", irish_grid_reference(&499999.9, &499999.9, 6).unwrap());
+    }
+
+    #[test]
+    fn test_bad_irish_grid_reference() {
+        assert_eq!(Err(ConversionError::InvalidGridReference),
+                   irish_grid_reference(&315904., &234671., 7));
+        assert_eq!(Err(ConversionError::InvalidGridReference),
+                   irish_grid_reference(&315904., &234671., 12));
+        assert!(irish_grid_reference(&500000., &234671., 8).is_err());
+        assert!(irish_grid_reference(&315904., &500000., 8).is_err());
+        assert_eq!("E 99999 99999",
+                   irish_grid_reference(&499999.9995, &499999.9995, 10).unwrap());
+    }
+
+    #[test]
+    fn test_os_grid_reference() {
+        assert_eq!("TQ 16276 73141", os_grid_reference(&516276., &173141., 10, true).unwrap());
+        assert_eq!("TQ1627673141", os_grid_reference(&516276., &173141., 10, false).unwrap());
+        assert_eq!("TG 5140 1317", os_grid_reference(&651409.903, &313177.270, 8, true).unwrap());
+        // Truncated, not rounded
+        assert_eq!("TQ 1 7", os_grid_reference(&519999.9, &179999.9, 2, true).unwrap());
+        assert_eq!("NN 166 712", os_grid_reference(&216600., &771200., 6, true).unwrap());
+        assert_eq!("SV 00 00", os_grid_reference(&0., &0., 4, true).unwrap());
+        assert_eq!("TW 99 99", os_grid_reference(&699999., &99999., 4, true).unwrap());
+        // Just short of the eastern edge of the grid
+        assert_eq!("TW 99999 99999",
+                   os_grid_reference(&699999.9995, &99999.9995, 10, true).unwrap());
+    }
+
+    #[test]
+    fn test_northern_squares() {
+        // Out Stack, Shetland, and the northern edge of the grid
+        assert_eq!("HP 6 1", os_grid_reference(&464000., &1219000., 2, true).unwrap());
+        assert_eq!("HL 00 50", os_grid_reference(&0., &1250000., 4, true).unwrap());
+        assert_eq!("JM 99 50", os_grid_reference(&699999., &1250000., 4, true).unwrap());
+        assert!(os_grid_reference(&464000., &1250000.001, 4, true).is_err());
+    }
+
+    #[test]
+    fn test_bad_os_grid_reference() {
+        assert_eq!(Err(ConversionError::InvalidGridReference),
+                   os_grid_reference(&516276., &173141., 5, true));
+        assert_eq!(Err(ConversionError::OutOfBounds {
+                       axis: Axis::Easting,
+                       value: 700000.,
+                       min: 0.,
+                       max: MAX_EASTING,
+                   }),
+                   os_grid_reference(&700000., &173141., 4, true));
+        assert!(os_grid_reference(&-1., &173141., 4, true).is_err());
+        let refs = os_grid_reference_threaded_vec(&[516276., f64::NAN], &[173141., 0.], 4, false);
+        assert_eq!(Ok("TQ1673".to_string()), refs[0]);
+        assert!(refs[1].is_err());
+    }
+
+    #[test]
+    fn test_parse_irish_grid_reference() {
+        assert_eq!(Ok((315900., 234670.)), parse_irish_grid_reference("O 1590 3467"));
+        assert_eq!(Ok((315900., 234670.)), parse_irish_grid_reference("o15903467"));
+        assert_eq!(Ok((300000., 200000.)), parse_irish_grid_reference("O"));
+        assert_eq!(Err(ConversionError::InvalidGridReference),
+                   parse_irish_grid_reference("I 1590 3467"));
+        assert_eq!(Err(ConversionError::InvalidGridReference),
+                   parse_irish_grid_reference("O 1590 346"));
+    }
+}
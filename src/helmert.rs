@@ -0,0 +1,241 @@
+//! This module provides seven-parameter Helmert transformations between ECEF cartesian coordinates
+//!
+//! The transformation uses the small-angle approximation to the rotation matrix, as in
+//! the OS [transformation user guide](https://www.ordnancesurvey.co.uk/docs/support/guide-coordinate-systems-great-britain.pdf)
+use conversions::{PI, RXS, RYS, RZS, S, TX, TY, TZ};
+use ecef::{ecef_to_geodetic, geodetic_to_ecef};
+use ellipsoid::Ellipsoid;
+use errors::ConversionError;
+
+/// A seven-parameter Helmert transformation
+///
+/// Translations are in metres, rotations in seconds of arc, and the scale change in parts per million
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Helmert {
+    pub tx: f64,
+    pub ty: f64,
+    pub tz: f64,
+    pub rx: f64,
+    pub ry: f64,
+    pub rz: f64,
+    pub s: f64,
+}
+
+impl Helmert {
+    pub fn new(tx: f64, ty: f64, tz: f64, rx: f64, ry: f64, rz: f64, s: f64) -> Helmert {
+        Helmert {
+            tx: tx,
+            ty: ty,
+            tz: tz,
+            rx: rx,
+            ry: ry,
+            rz: rz,
+            s: s,
+        }
+    }
+
+    /// The published ETRS89 (GRS80) to OSGB36 (Airy 1830) parameters
+    pub fn etrs89_to_osgb36() -> Helmert {
+        Helmert::new(TX, TY, TZ, RXS, RYS, RZS, S * 1000000.)
+    }
+
+    /// The published OSGB36 to ETRS89 parameters: those of
+    /// [`etrs89_to_osgb36`](#method.etrs89_to_osgb36) with their signs reversed
+    pub fn osgb36_to_etrs89() -> Helmert {
+        Helmert::etrs89_to_osgb36().reversed()
+    }
+
+    /// The published ETRS89 (GRS80) to Irish Grid (Airy Modified) parameters, from OSi / OSNI
+    pub fn etrs89_to_irish_grid() -> Helmert {
+        Helmert::new(-482.530, 130.596, -564.557, 1.042, 0.214, 0.631, -8.150)
+    }
+
+    /// The same transformation with the signs of all seven parameters reversed
+    ///
+    /// This is the conventional approximate inverse; see
+    /// [`inverse_transform`](#method.inverse_transform) for the exact one
+    pub fn reversed(&self) -> Helmert {
+        Helmert::new(-self.tx,
+                     -self.ty,
+                     -self.tz,
+                     -self.rx,
+                     -self.ry,
+                     -self.rz,
+                     -self.s)
+    }
+
+    // The matrix which scales and rotates the input point
+    fn matrix(&self) -> [[f64; 3]; 3] {
+        let rx = self.rx * PI / (180. * 3600.);
+        let ry = self.ry * PI / (180. * 3600.);
+        let rz = self.rz * PI / (180. * 3600.);
+        let s = 1. + self.s * 0.000001;
+        [[s, -rz, ry], [rz, s, -rx], [-ry, rx, s]]
+    }
+
+    /// Apply the transformation to ECEF X, Y, Z (m)
+    pub fn transform(&self, x: &f64, y: &f64, z: &f64) -> (f64, f64, f64) {
+        let m = self.matrix();
+        (self.tx + m[0][0] * x + m[0][1] * y + m[0][2] * z,
+         self.ty + m[1][0] * x + m[1][1] * y + m[1][2] * z,
+         self.tz + m[2][0] * x + m[2][1] * y + m[2][2] * z)
+    }
+
+    /// Undo the transformation exactly, by inverting its matrix,
+    /// so that `inverse_transform` recovers the input to [`transform`](#method.transform)
+    pub fn inverse_transform(&self, x: &f64, y: &f64, z: &f64) -> (f64, f64, f64) {
+        let m = self.matrix();
+        let (x, y, z) = (x - self.tx, y - self.ty, z - self.tz);
+        // The inverse is the adjugate (transposed cofactor matrix) divided by the determinant
+        let adj = [[m[1][1] * m[2][2] - m[1][2] * m[2][1],
+                    m[0][2] * m[2][1] - m[0][1] * m[2][2],
+                    m[0][1] * m[1][2] - m[0][2] * m[1][1]],
+                   [m[1][2] * m[2][0] - m[1][0] * m[2][2],
+                    m[0][0] * m[2][2] - m[0][2] * m[2][0],
+                    m[0][2] * m[1][0] - m[0][0] * m[1][2]],
+                   [m[1][0] * m[2][1] - m[1][1] * m[2][0],
+                    m[0][1] * m[2][0] - m[0][0] * m[2][1],
+                    m[0][0] * m[1][1] - m[0][1] * m[1][0]]];
+        let det = m[0][0] * adj[0][0] + m[0][1] * adj[1][0] + m[0][2] * adj[2][0];
+        ((adj[0][0] * x + adj[0][1] * y + adj[0][2] * z) / det,
+         (adj[1][0] * x + adj[1][1] * y + adj[1][2] * z) / det,
+         (adj[2][0] * x + adj[2][1] * y + adj[2][2] * z) / det)
+    }
+
+    /// Apply the transformation to a longitude, latitude (decimal degrees) and ellipsoidal height (m)
+    /// on the `from` ellipsoid, returning a longitude, latitude and height on the `to` ellipsoid
+    pub fn transform_geodetic(&self,
+                              longitude: &f64,
+                              latitude: &f64,
+                              height: &f64,
+                              from: &Ellipsoid,
+                              to: &Ellipsoid)
+                              -> Result<(f64, f64, f64), ConversionError> {
+        let (x, y, z) = geodetic_to_ecef(longitude, latitude, height, from)?;
+        let (x, y, z) = self.transform(&x, &y, &z);
+        ecef_to_geodetic(&x, &y, &z, to)
+    }
+
+    /// Undo the transformation exactly for a longitude, latitude (decimal degrees) and
+    /// ellipsoidal height (m) on the `from` ellipsoid, returning a longitude, latitude and height
+    /// on the `to` ellipsoid
+    pub fn inverse_transform_geodetic(&self,
+                                      longitude: &f64,
+                                      latitude: &f64,
+                                      height: &f64,
+                                      from: &Ellipsoid,
+                                      to: &Ellipsoid)
+                                      -> Result<(f64, f64, f64), ConversionError> {
+        let (x, y, z) = geodetic_to_ecef(longitude, latitude, height, from)?;
+        let (x, y, z) = self.inverse_transform(&x, &y, &z);
+        ecef_to_geodetic(&x, &y, &z, to)
+    }
+}
+
+/// A fourteen-parameter (time-dependent) Helmert transformation
+///
+/// The seven parameters at a coordinate epoch `t` (decimal years) are
+/// `helmert + rates * (t - reference_epoch)`, using the same units as [`Helmert`](struct.Helmert.html),
+/// per year for the rates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeDependentHelmert {
+    pub helmert: Helmert,
+    pub rates: Helmert,
+    pub reference_epoch: f64,
+}
+
+impl TimeDependentHelmert {
+    pub fn new(helmert: Helmert, rates: Helmert, reference_epoch: f64) -> TimeDependentHelmert {
+        TimeDependentHelmert {
+            helmert: helmert,
+            rates: rates,
+            reference_epoch: reference_epoch,
+        }
+    }
+
+    /// The seven-parameter transformation which applies at `epoch` (decimal years)
+    pub fn at_epoch(&self, epoch: &f64) -> Helmert {
+        let dt = epoch - self.reference_epoch;
+        let (p, r) = (&self.helmert, &self.rates);
+        Helmert::new(p.tx + r.tx * dt,
+                     p.ty + r.ty * dt,
+                     p.tz + r.tz * dt,
+                     p.rx + r.rx * dt,
+                     p.ry + r.ry * dt,
+                     p.rz + r.rz * dt,
+                     p.s + r.s * dt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osgb36_parameters() {
+        let helmert = Helmert::etrs89_to_osgb36();
+        assert_eq!(-446.448, helmert.tx);
+        assert_eq!(-0.8421, helmert.rz);
+        assert!((helmert.s - 20.4894).abs() < 0.000000001);
+        assert_eq!(446.448, Helmert::osgb36_to_etrs89().tx);
+    }
+
+    #[test]
+    fn test_exact_inverse() {
+        let helmert = Helmert::etrs89_to_osgb36();
+        let (x, y, z) = helmert.transform(&3874938.849, &116218.624, &5047168.208);
+        let (x, y, z) = helmert.inverse_transform(&x, &y, &z);
+        assert!((x - 3874938.849).abs() < 0.000001);
+        assert!((y - 116218.624).abs() < 0.000001);
+        assert!((z - 5047168.208).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_reversed_parameters_are_approximate() {
+        // Reversing the signs doesn't undo the transformation exactly, but is within a few cm
+        let helmert = Helmert::etrs89_to_osgb36();
+        let (x, y, z) = helmert.transform(&3874938.849, &116218.624, &5047168.208);
+        let (x, y, z) = helmert.reversed().transform(&x, &y, &z);
+        assert!((x - 3874938.849).abs() < 0.05);
+        assert!((y - 116218.624).abs() < 0.05);
+        assert!((z - 5047168.208).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_custom_parameters() {
+        // A pure translation
+        let helmert = Helmert::new(1., 2., 3., 0., 0., 0., 0.);
+        assert_eq!((11., 22., 33.), helmert.transform(&10., &20., &30.));
+        assert_eq!((10., 20., 30.), helmert.inverse_transform(&11., &22., &33.));
+    }
+
+    #[test]
+    fn test_at_epoch() {
+        let helmert = TimeDependentHelmert::new(Helmert::new(1., 0., 0., 0., 0., 0.5, 2.),
+                                                Helmert::new(0.1, 0., 0., 0., 0., -0.01, 0.),
+                                                2010.);
+        assert_eq!(Helmert::new(1.5, 0., 0., 0., 0., 0.45, 2.),
+                   helmert.at_epoch(&2015.));
+        assert_eq!(helmert.helmert, helmert.at_epoch(&2010.));
+    }
+
+    #[test]
+    fn test_transform_geodetic() {
+        let helmert = Helmert::etrs89_to_osgb36();
+        let (lon, lat, h) = helmert.transform_geodetic(&-0.32824866,
+                                &51.44533267,
+                                &0.,
+                                &Ellipsoid::GRS80,
+                                &Ellipsoid::AIRY_1830)
+            .unwrap();
+        let (lon, lat, h) = helmert.inverse_transform_geodetic(&lon,
+                                        &lat,
+                                        &h,
+                                        &Ellipsoid::AIRY_1830,
+                                        &Ellipsoid::GRS80)
+            .unwrap();
+        assert!((lon - -0.32824866).abs() < 0.000000001);
+        assert!((lat - 51.44533267).abs() < 0.000000001);
+        assert!(h.abs() < 0.001);
+    }
+}
@@ -0,0 +1,142 @@
+//! This module provides conversions between ETRS89 longitude and latitude and the two
+//! Irish grids: Irish Transverse Mercator (ITM, EPSG:2157), and the Irish Grid (EPSG:29903)
+//!
+//! ITM is a Transverse Mercator projection of ETRS89 (IRENET95) on the GRS80 ellipsoid.
+//! The Irish Grid uses the Airy Modified ellipsoid, and is reached from ETRS89 by the
+//! seven-parameter Helmert transformation published by OSi and OSNI, which is accurate to
+//! around 1 m.
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use helmert::Helmert;
+use transverse_mercator::TransverseMercator;
+use utils::{check, round_to_eight, ToMm};
+
+// Ireland and Northern Ireland, including territorial waters
+pub const MIN_IRISH_LONGITUDE: f64 = -10.5600;
+pub const MAX_IRISH_LONGITUDE: f64 = -5.3400;
+pub const MIN_IRISH_LATITUDE: f64 = 51.3900;
+pub const MAX_IRISH_LATITUDE: f64 = 55.4300;
+// The 500 km square covered by the Irish Grid's 100 km squares
+pub const MAX_IRISH_GRID_EASTING: f64 = 500000.000;
+pub const MAX_IRISH_GRID_NORTHING: f64 = 500000.000;
+// The same square, in ITM coordinates
+pub const MIN_ITM_EASTING: f64 = 400000.000;
+pub const MAX_ITM_EASTING: f64 = 900000.000;
+pub const MIN_ITM_NORTHING: f64 = 500000.000;
+pub const MAX_ITM_NORTHING: f64 = 1000000.000;
+
+/// Perform ETRS89 Longitude, Latitude to Irish Transverse Mercator (EPSG:2157) conversion
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::convert_itm;
+/// let (easting, northing) = convert_itm(&-6.260254, &53.349805).unwrap();
+/// ```
+pub fn convert_itm(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ConversionError> {
+    let lon = check(*longitude,
+                    (MIN_IRISH_LONGITUDE, MAX_IRISH_LONGITUDE),
+                    Axis::Longitude)?;
+    let lat = check(*latitude, (MIN_IRISH_LATITUDE, MAX_IRISH_LATITUDE), Axis::Latitude)?;
+    let (east, north) = TransverseMercator::ITM.forward(&lon, &lat)?;
+    Ok((east.round_to_mm(), north.round_to_mm()))
+}
+
+/// Convert Irish Transverse Mercator (EPSG:2157) coordinates to ETRS89 Lon, Lat
+#[allow(non_snake_case)]
+pub fn convert_itm_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
+    check(*E, (MIN_ITM_EASTING, MAX_ITM_EASTING), Axis::Easting)?;
+    check(*N, (MIN_ITM_NORTHING, MAX_ITM_NORTHING), Axis::Northing)?;
+    let (lon, lat) = TransverseMercator::ITM.inverse(E, N)?;
+    Ok(round_to_eight(lon, lat))
+}
+
+/// Perform ETRS89 Longitude, Latitude to Irish Grid (EPSG:29903) conversion
+pub fn convert_irish_grid(longitude: &f64, latitude: &f64) -> Result<(f64, f64), ConversionError> {
+    let lon = check(*longitude,
+                    (MIN_IRISH_LONGITUDE, MAX_IRISH_LONGITUDE),
+                    Axis::Longitude)?;
+    let lat = check(*latitude, (MIN_IRISH_LATITUDE, MAX_IRISH_LATITUDE), Axis::Latitude)?;
+    let (lon, lat, _) = Helmert::etrs89_to_irish_grid().transform_geodetic(&lon,
+                                        &lat,
+                                        &0.,
+                                        &Ellipsoid::GRS80,
+                                        &Ellipsoid::AIRY_MODIFIED)?;
+    let (east, north) = TransverseMercator::IRISH_GRID.forward(&lon, &lat)?;
+    Ok((east.round_to_mm(), north.round_to_mm()))
+}
+
+/// Convert Irish Grid (EPSG:29903) coordinates to ETRS89 Lon, Lat
+///
+/// The Helmert transformation is inverted exactly, so that this function reverses
+/// [`convert_irish_grid`](fn.convert_irish_grid.html)
+#[allow(non_snake_case)]
+pub fn convert_irish_grid_to_ll(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
+    check(*E, (0.000, MAX_IRISH_GRID_EASTING), Axis::Easting)?;
+    check(*N, (0.000, MAX_IRISH_GRID_NORTHING), Axis::Northing)?;
+    let (lon, lat) = TransverseMercator::IRISH_GRID.inverse(E, N)?;
+    let (lon, lat, _) = Helmert::etrs89_to_irish_grid().inverse_transform_geodetic(&lon,
+                                                &lat,
+                                                &0.,
+                                                &Ellipsoid::AIRY_MODIFIED,
+                                                &Ellipsoid::GRS80)?;
+    Ok(round_to_eight(lon, lat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The ETRS89 position of the Spire, O'Connell Street, Dublin. The expected coordinates were
+    // computed independently of this crate from the published OSi / OSNI projection and
+    // Helmert parameters, using Krüger's series to sixth order and a position vector Helmert
+    // transformation via ECEF coordinates
+    const SPIRE: (f64, f64) = (-6.260254, 53.349805);
+    const SPIRE_ITM: (f64, f64) = (715829.555, 734698.224);
+    const SPIRE_IRISH_GRID: (f64, f64) = (315903.602, 234672.039);
+
+    #[test]
+    fn test_itm_conversion() {
+        let (e, n) = convert_itm(&SPIRE.0, &SPIRE.1).unwrap();
+        assert!((e - SPIRE_ITM.0).abs() <= 0.001);
+        assert!((n - SPIRE_ITM.1).abs() <= 0.001);
+        let (lon, lat) = convert_itm_to_ll(&SPIRE_ITM.0, &SPIRE_ITM.1).unwrap();
+        assert!((lon - SPIRE.0).abs() < 0.00000002);
+        assert!((lat - SPIRE.1).abs() < 0.00000002);
+    }
+
+    #[test]
+    fn test_itm_origin() {
+        // The published true origin of ITM
+        assert_eq!((600000., 750000.), convert_itm(&-8., &53.5).unwrap());
+        assert_eq!((-8., 53.5), convert_itm_to_ll(&600000., &750000.).unwrap());
+    }
+
+    #[test]
+    fn test_irish_grid_conversion() {
+        let (e, n) = convert_irish_grid(&SPIRE.0, &SPIRE.1).unwrap();
+        assert!((e - SPIRE_IRISH_GRID.0).abs() <= 0.001);
+        assert!((n - SPIRE_IRISH_GRID.1).abs() <= 0.001);
+        let (lon, lat) = convert_irish_grid_to_ll(&SPIRE_IRISH_GRID.0, &SPIRE_IRISH_GRID.1)
+            .unwrap();
+        assert!((lon - SPIRE.0).abs() < 0.00000002);
+        assert!((lat - SPIRE.1).abs() < 0.00000002);
+    }
+
+    #[test]
+    fn test_irish_grid_origin() {
+        // The published true origin of the Irish Grid, on the Airy Modified ellipsoid
+        assert_eq!((200000., 250000.),
+                   TransverseMercator::IRISH_GRID.forward(&-8., &53.5).unwrap());
+    }
+
+    #[test]
+    fn test_outside_ireland() {
+        // Caister Water Tower, Norfolk
+        match convert_itm(&1.716073973, &52.658007833) {
+            Err(ConversionError::OutOfBounds { axis: Axis::Longitude, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(convert_irish_grid_to_ll(&500000.001, &234671.).is_err());
+    }
+}
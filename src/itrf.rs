@@ -0,0 +1,151 @@
+//! This module provides transformations from global reference frames (ITRF, and the WGS84
+//! realisations aligned to them) to ETRS89, as realised by ETRF2000
+//!
+//! ETRS89 is fixed to the Eurasian plate, so ITRF and WGS84 coordinates of a point in the UK
+//! drift away from its ETRS89 coordinates by around 2.5 cm per year, and differ by
+//! about 80 cm today. The transformations use the fourteen-parameter model from
+//! [EUREF Technical Note 1](http://etrs89.ensg.ign.fr/pub/EUREF-TN-1.pdf).
+//! Intraplate velocities in Great Britain are small, so the ETRS89 coordinates are
+//! used as-is by the OSTN15 conversions, whatever their epoch.
+use conversions::convert_osgb36;
+use ecef::{ecef_to_geodetic, geodetic_to_ecef};
+use ellipsoid::Ellipsoid;
+use errors::ConversionError;
+use helmert::{Helmert, TimeDependentHelmert};
+use super::convert_vec_direct;
+
+/// A global terrestrial reference frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceFrame {
+    Itrf2014,
+    Itrf2020,
+    /// WGS84 (G2139), which is aligned to ITRF2014 at the centimetre level
+    Wgs84G2139,
+    /// WGS84 (G2296), which is aligned to ITRF2020 at the centimetre level
+    Wgs84G2296,
+}
+
+impl ReferenceFrame {
+    /// The transformation from this frame to ETRF2000
+    ///
+    /// Parameters are published in mm, mas and ppb, and are converted to
+    /// the units used by [`Helmert`](struct.Helmert.html)
+    pub fn to_etrf2000(&self) -> TimeDependentHelmert {
+        match *self {
+            ReferenceFrame::Itrf2014 |
+            ReferenceFrame::Wgs84G2139 => {
+                euref(&[54.7, 52.2, -74.1, 1.701, 10.290, -16.632, 2.12],
+                      &[0.1, 0.1, -1.9, 0.081, 0.490, -0.792, 0.11],
+                      2010.0)
+            }
+            ReferenceFrame::Itrf2020 |
+            ReferenceFrame::Wgs84G2296 => {
+                euref(&[53.8, 51.8, -82.2, 2.106, 12.740, -20.592, 2.25],
+                      &[0.1, 0.0, -1.7, 0.081, 0.490, -0.792, 0.11],
+                      2015.0)
+            }
+        }
+    }
+}
+
+// Build a transformation from parameters given as tx, ty, tz (mm), rx, ry, rz (mas), s (ppb)
+fn euref(params: &[f64; 7], rates: &[f64; 7], reference_epoch: f64) -> TimeDependentHelmert {
+    let scaled = |p: &[f64; 7]| {
+        Helmert::new(p[0] / 1000.,
+                     p[1] / 1000.,
+                     p[2] / 1000.,
+                     p[3] / 1000.,
+                     p[4] / 1000.,
+                     p[5] / 1000.,
+                     p[6] / 1000.)
+    };
+    TimeDependentHelmert::new(scaled(params), scaled(rates), reference_epoch)
+}
+
+/// Convert a longitude, latitude (decimal degrees) and ellipsoidal height (m) in `frame`
+/// at coordinate epoch `epoch` (decimal years, e.g. `2024.5`) to ETRS89
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{convert_itrf_to_etrs89, ReferenceFrame};
+/// let (lon, lat, h) =
+///     convert_itrf_to_etrs89(&-0.32824866, &51.44533267, &0., ReferenceFrame::Itrf2014, &2024.)
+///         .unwrap();
+/// ```
+pub fn convert_itrf_to_etrs89(longitude: &f64,
+                              latitude: &f64,
+                              height: &f64,
+                              frame: ReferenceFrame,
+                              epoch: &f64)
+                              -> Result<(f64, f64, f64), ConversionError> {
+    let (x, y, z) = geodetic_to_ecef(longitude, latitude, height, &Ellipsoid::GRS80)?;
+    let (x, y, z) = frame.to_etrf2000().at_epoch(epoch).transform(&x, &y, &z);
+    ecef_to_geodetic(&x, &y, &z, &Ellipsoid::GRS80)
+}
+
+/// Convert a longitude and latitude (decimal degrees) in `frame` at coordinate epoch
+/// `epoch` (decimal years) to OSGB36 Eastings and Northings, using OSTN15 data
+pub fn convert_itrf_to_osgb36(longitude: &f64,
+                              latitude: &f64,
+                              frame: ReferenceFrame,
+                              epoch: &f64)
+                              -> Result<(f64, f64), ConversionError> {
+    let (lon, lat, _) = convert_itrf_to_etrs89(longitude, latitude, &0., frame, epoch)?;
+    convert_osgb36(&lon, &lat)
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_itrf_to_osgb36`](fn.convert_itrf_to_osgb36.html)
+pub fn convert_itrf_to_osgb36_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                               latitudes: &'a mut [f64],
+                                               frame: ReferenceFrame,
+                                               epoch: &f64)
+                                               -> (&'a mut [f64], &'a mut [f64]) {
+    let epoch = *epoch;
+    convert_vec_direct(longitudes,
+                       latitudes,
+                       move |lon: &f64, lat: &f64| convert_itrf_to_osgb36(lon, lat, frame, &epoch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_itrf2014_drift() {
+        // ITRF2014 and ETRS89 were 0.2-0.3 m apart in 1989, and move apart by ~2.5 cm per year
+        let shift = |epoch: f64| {
+            let (x, y, z) = geodetic_to_ecef(&-2., &54., &0., &Ellipsoid::GRS80).unwrap();
+            let (xe, ye, ze) =
+                ReferenceFrame::Itrf2014.to_etrf2000().at_epoch(&epoch).transform(&x, &y, &z);
+            ((xe - x).powi(2) + (ye - y).powi(2) + (ze - z).powi(2)).sqrt()
+        };
+        assert!(shift(2024.) > 0.7 && shift(2024.) < 0.9);
+        assert!(shift(2024.) - shift(2014.) > 0.2);
+    }
+
+    #[test]
+    fn test_frames_agree() {
+        // ITRF2014 and ITRF2020 differ by millimetres
+        let a = convert_itrf_to_etrs89(&-2., &54., &0., ReferenceFrame::Itrf2014, &2020.).unwrap();
+        let b = convert_itrf_to_etrs89(&-2., &54., &0., ReferenceFrame::Itrf2020, &2020.).unwrap();
+        assert!((a.0 - b.0).abs() < 0.0000002);
+        assert!((a.1 - b.1).abs() < 0.0000002);
+        assert!((a.2 - b.2).abs() < 0.01);
+        let c = convert_itrf_to_etrs89(&-2., &54., &0., ReferenceFrame::Wgs84G2139, &2020.);
+        assert_eq!(a, c.unwrap());
+    }
+
+    #[test]
+    fn test_threaded_itrf_to_osgb36() {
+        let lons: &mut [f64] = &mut [-0.32824866, 181.];
+        let lats: &mut [f64] = &mut [51.44533267, 51.44533267];
+        let (eastings, northings) =
+            convert_itrf_to_osgb36_threaded_vec(lons, lats, ReferenceFrame::Itrf2020, &2024.);
+        let expected =
+            convert_itrf_to_osgb36(&-0.32824866, &51.44533267, ReferenceFrame::Itrf2020, &2024.)
+                .unwrap();
+        assert_eq!(expected, (eastings[0], northings[0]));
+        assert!(eastings[1].is_nan());
+    }
+}
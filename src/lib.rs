@@ -18,6 +18,21 @@
 //! lonlat_bng::convert_to_osgb36_threaded_vec(vec![&-0.32824866], vec![&51.44533267]);
 //! lonlat_bng::convert_osgb36_to_lonlat_threaded_vec(vec![&516276], vec![&173141]);
 //! ```
+//! ```
+//! // Use typed coordinates, so that e.g. ETRS89 and OSGB36 grid coordinates can't be confused
+//! use std::convert::TryFrom;
+//! use lonlat_bng::{LonLat, Osgb36Grid};
+//! let bng = Osgb36Grid::try_from(LonLat::new(1.716073973, 52.658007833)).unwrap();
+//! assert_eq!(651409.804, bng.easting());
+//! ```
+//! WGS84 and ETRS89 are treated as equivalent by the functions above. They are now
+//! nearly a metre apart, so coordinates in a global frame at a known epoch should first be
+//! transformed using [`convert_itrf_to_etrs89`](fn.convert_itrf_to_etrs89.html),
+//! or converted directly using [`convert_itrf_to_osgb36`](fn.convert_itrf_to_osgb36.html).
+//!
+//! The single-point functions return a [`ConversionError`](enum.ConversionError.html) describing why a conversion failed,
+//! e.g. an out-of-bounds latitude, or a point which falls outside OSTN15 coverage.
+//!
 //! The crate also provides C-compatible wrapper functions which are intended for use with FFI.
 //!
 //! **An example FFI implementation using Python can be found at [Convertbng](https://github.com/urschrei/convertbng)**.
@@ -33,9 +48,28 @@ use rayon::prelude::*;
 mod conversions;
 pub mod utils;
 mod ffi;
+mod errors;
+mod coordinates;
+mod crs;
+mod datum;
+mod ellipsoid;
+mod ecef;
+mod helmert;
+mod itrf;
+mod transverse_mercator;
+mod irish;
+mod grid_ref;
+mod utm;
+mod tiles;
+mod tile_matrix;
+mod scale;
+mod bearing;
+mod geodesic;
+mod area;
 
 pub use ffi::Array;
 pub use ffi::drop_float_array;
+pub use ffi::drop_string_array;
 pub use ffi::convert_to_bng_threaded;
 pub use ffi::convert_to_lonlat_threaded;
 pub use ffi::convert_to_osgb36_threaded;
@@ -45,6 +79,43 @@ pub use ffi::convert_etrs89_to_ll_threaded;
 pub use ffi::convert_osgb36_to_ll_threaded;
 pub use ffi::convert_osgb36_to_etrs89_threaded;
 pub use ffi::convert_epsg3857_to_wgs84_threaded;
+pub use ffi::convert_wgs84_to_epsg3857_threaded;
+pub use ffi::convert_osgb36_to_epsg3857_threaded;
+pub use ffi::convert_epsg3857_to_osgb36_threaded;
+pub use ffi::scale_and_convergence_threaded;
+pub use ffi::scale_and_convergence_osgb36_threaded;
+pub use ffi::convert_to_itm_threaded;
+pub use ffi::convert_itm_to_ll_threaded;
+pub use ffi::convert_to_irish_grid_threaded;
+pub use ffi::convert_irish_grid_to_ll_threaded;
+pub use ffi::convert_to_osgb36_3d_threaded;
+pub use ffi::convert_etrs89_to_osgb36_3d_threaded;
+pub use ffi::convert_osgb36_to_etrs89_3d_threaded;
+pub use ffi::convert_osgb36_to_ll_3d_threaded;
+pub use ffi::datum_flags_from_file;
+pub use ffi::drop_datum_flags;
+pub use ffi::convert_to_osgb36_3d_threaded_with_datum;
+pub use ffi::convert_etrs89_to_osgb36_3d_threaded_with_datum;
+pub use ffi::convert_osgb36_to_etrs89_3d_threaded_with_datum;
+pub use ffi::convert_ecef_to_osgb36_threaded;
+pub use ffi::convert_epsg_threaded;
+pub use ffi::convert_epsg_threaded_with_status;
+pub use ffi::convert_epsg_3d_threaded;
+pub use ffi::geodesic_inverse_threaded;
+pub use ffi::geodesic_direct_threaded;
+pub use ffi::epsg_pair_supported;
+pub use ffi::epsg_pair_supported_3d;
+pub use ffi::convert_to_osgb36_threaded_with_status;
+pub use ffi::convert_to_etrs89_threaded_with_status;
+pub use ffi::convert_etrs89_to_osgb36_threaded_with_status;
+pub use ffi::convert_etrs89_to_ll_threaded_with_status;
+pub use ffi::convert_osgb36_to_etrs89_threaded_with_status;
+pub use ffi::convert_osgb36_to_ll_threaded_with_status;
+pub use ffi::convert_epsg3857_to_wgs84_threaded_with_status;
+pub use ffi::convert_wgs84_to_epsg3857_threaded_with_status;
+pub use ffi::convert_osgb36_to_epsg3857_threaded_with_status;
+pub use ffi::convert_epsg3857_to_osgb36_threaded_with_status;
+pub use ffi::os_grid_reference_threaded;
 
 pub use conversions::convert_etrs89;
 pub use conversions::convert_osgb36;
@@ -53,71 +124,407 @@ pub use conversions::convert_osgb36_to_etrs89;
 pub use conversions::convert_osgb36_to_ll;
 pub use conversions::convert_etrs89_to_ll;
 pub use conversions::convert_epsg3857_to_wgs84;
+pub use conversions::convert_wgs84_to_epsg3857;
+pub use conversions::convert_osgb36_to_epsg3857;
+pub use conversions::convert_epsg3857_to_osgb36;
+pub use conversions::convert_osgb36_3d;
+pub use conversions::convert_etrs89_to_osgb36_3d;
+pub use conversions::convert_osgb36_to_etrs89_3d;
+pub use conversions::convert_osgb36_to_ll_3d;
+pub use conversions::convert_osgb36_3d_with_datum;
+pub use conversions::convert_etrs89_to_osgb36_3d_with_datum;
+pub use conversions::convert_osgb36_to_etrs89_3d_with_datum;
+
+pub use datum::DatumFlags;
+pub use datum::VerticalDatum;
+
+pub use ellipsoid::Ellipsoid;
+
+pub use helmert::Helmert;
+pub use helmert::TimeDependentHelmert;
+
+pub use transverse_mercator::TransverseMercator;
+pub use transverse_mercator::TmSeries;
+
+pub use irish::convert_itm;
+pub use irish::convert_itm_to_ll;
+pub use irish::convert_irish_grid;
+pub use irish::convert_irish_grid_to_ll;
+
+pub use tiles::BoundingBox;
+pub use tiles::Tile;
+pub use tiles::TileScheme;
+pub use tiles::tiles_covering_osgb36;
+
+pub use tile_matrix::TileIndex;
+pub use tile_matrix::TileMatrixSet;
+
+pub use scale::scale_and_convergence;
+pub use scale::scale_and_convergence_osgb36;
+pub use scale::scale_and_convergence_threaded_vec;
+pub use scale::scale_and_convergence_osgb36_threaded_vec;
+pub use scale::LineScale;
+pub use scale::line_scale_osgb36;
+
+pub use bearing::Dms;
+pub use bearing::true_to_grid_bearing;
+pub use bearing::grid_to_true_bearing;
+pub use bearing::true_to_grid_bearing_osgb36;
+pub use bearing::grid_to_true_bearing_osgb36;
+
+pub use geodesic::geodesic_inverse;
+pub use geodesic::geodesic_direct;
+pub use geodesic::geodesic_polygon_area;
+pub use geodesic::geodesic_inverse_threaded_vec;
+pub use geodesic::geodesic_path_threaded_vec;
+pub use geodesic::geodesic_direct_threaded_vec;
+
+pub use area::Polygon;
+pub use area::PolygonArea;
+pub use area::polygon_area;
+pub use area::polygon_area_osgb36;
+pub use area::multipolygon_area;
+pub use area::multipolygon_area_osgb36;
+
+pub use utm::UtmZone;
+pub use utm::convert_utm;
+pub use utm::convert_utm_to_ll;
+pub use utm::convert_utm_to_osgb36;
+pub use utm::convert_osgb36_to_utm;
+pub use utm::convert_to_utm_threaded_vec;
+pub use utm::convert_utm_to_ll_threaded_vec;
+pub use utm::convert_utm_to_osgb36_threaded_vec;
+pub use utm::convert_osgb36_to_utm_threaded_vec;
+
+pub use grid_ref::os_grid_reference;
+pub use grid_ref::os_grid_reference_threaded_vec;
+pub use grid_ref::irish_grid_reference;
+pub use grid_ref::parse_irish_grid_reference;
+
+pub use itrf::ReferenceFrame;
+pub use itrf::convert_itrf_to_etrs89;
+pub use itrf::convert_itrf_to_osgb36;
+pub use itrf::convert_itrf_to_osgb36_threaded_vec;
+
+pub use ecef::geodetic_to_ecef;
+pub use ecef::ecef_to_geodetic;
+pub use ecef::convert_ecef_to_osgb36;
+pub use ecef::geodetic_to_ecef_threaded_vec;
+pub use ecef::ecef_to_geodetic_threaded_vec;
+pub use ecef::convert_ecef_to_osgb36_threaded_vec;
+
+pub use errors::Axis;
+pub use errors::ConversionError;
+pub use errors::Status;
+
+pub use coordinates::LonLat;
+pub use coordinates::Etrs89Grid;
+pub use coordinates::Osgb36Grid;
+pub use coordinates::WebMercator;
+
+pub use crs::Crs;
+pub use crs::Transformer;
+pub use crs::transform;
+pub use crs::transform_3d;
+pub use crs::is_supported_epsg_pair;
+pub use crs::supported_epsg_pairs;
+pub use crs::is_supported_epsg_pair_3d;
+pub use crs::supported_epsg_pairs_3d;
 
 use std::f64;
 pub const NAN: f64 = f64::NAN;
 
-/// A threaded wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
-pub fn convert_to_bng_threaded_vec<'a>(longitudes: &'a mut [f64],
-                                       latitudes: &'a mut [f64])
-                                       -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(longitudes, latitudes, convert_osgb36)
+// Generate threaded functions which convert slices in place between two coordinate reference
+// systems. Each is an instance of Transformer::transform_threaded_vec
+macro_rules! transformer_batch {
+    ($($(#[$attr:meta])* fn $name:ident($x:ident, $y:ident): $from:expr => $to:expr;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name<'a>($x: &'a mut [f64],
+                             $y: &'a mut [f64])
+                             -> (&'a mut [f64], &'a mut [f64]) {
+                Transformer::new($from, $to).transform_threaded_vec($x, $y)
+            }
+        )*
+    }
+}
+
+// As transformer_batch, for Transformer::transform_threaded_vec_with_status
+macro_rules! transformer_batch_with_status {
+    ($($(#[$attr:meta])* fn $name:ident($x:ident, $y:ident, $status:ident):
+       $from:expr => $to:expr;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name<'a>($x: &'a mut [f64],
+                             $y: &'a mut [f64],
+                             $status: &'a mut [u8])
+                             -> (&'a mut [f64], &'a mut [f64]) {
+                Transformer::new($from, $to).transform_threaded_vec_with_status($x, $y, $status)
+            }
+        )*
+    }
+}
+
+transformer_batch! {
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
+    fn convert_to_bng_threaded_vec(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+    fn convert_to_lonlat_threaded_vec(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html)
+    fn convert_to_etrs89_threaded_vec(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Etrs89Grid;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
+    fn convert_to_osgb36_threaded_vec(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html)
+    fn convert_etrs89_to_osgb36_threaded_vec(eastings, northings):
+        Crs::Etrs89Grid => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs8989_to_ll.html)
+    fn convert_etrs89_to_ll_threaded_vec(eastings, northings):
+        Crs::Etrs89Grid => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html)
+    fn convert_osgb36_to_etrs89_threaded_vec(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89Grid;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
+    fn convert_osgb36_to_ll_threaded_vec(eastings, northings):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html)
+    fn convert_epsg3857_to_wgs84_threaded_vec(x, y):
+        Crs::WebMercator => Crs::Wgs84;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_wgs84_to_epsg3857`](fn.convert_wgs84_to_epsg3857.html)
+    fn convert_wgs84_to_epsg3857_threaded_vec(longitudes, latitudes):
+        Crs::Wgs84 => Crs::WebMercator;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_epsg3857`](fn.convert_osgb36_to_epsg3857.html)
+    fn convert_osgb36_to_epsg3857_threaded_vec(eastings, northings):
+        Crs::Osgb36 => Crs::WebMercator;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_epsg3857_to_osgb36`](fn.convert_epsg3857_to_osgb36.html)
+    fn convert_epsg3857_to_osgb36_threaded_vec(x, y):
+        Crs::WebMercator => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_itm`](fn.convert_itm.html)
+    fn convert_to_itm_threaded_vec(longitudes, latitudes):
+        Crs::Etrs89 => Crs::Itm;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_itm_to_ll`](fn.convert_itm_to_ll.html)
+    fn convert_itm_to_ll_threaded_vec(eastings, northings):
+        Crs::Itm => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_irish_grid`](fn.convert_irish_grid.html)
+    fn convert_to_irish_grid_threaded_vec(longitudes, latitudes):
+        Crs::Etrs89 => Crs::IrishGrid;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_irish_grid_to_ll`](fn.convert_irish_grid_to_ll.html)
+    fn convert_irish_grid_to_ll_threaded_vec(eastings, northings):
+        Crs::IrishGrid => Crs::Etrs89;
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
-pub fn convert_to_lonlat_threaded_vec<'a>(eastings: &'a mut [f64],
-                                          northings: &'a mut [f64])
-                                          -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(eastings, northings, convert_osgb36_to_ll)
+transformer_batch_with_status! {
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_to_osgb36_threaded_vec_with_status(longitudes, latitudes, status):
+        Crs::Etrs89 => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_to_etrs89_threaded_vec_with_status(longitudes, latitudes, status):
+        Crs::Etrs89 => Crs::Etrs89Grid;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_etrs89_to_osgb36_threaded_vec_with_status(eastings, northings, status):
+        Crs::Etrs89Grid => Crs::Osgb36;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs89_to_ll.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_etrs89_to_ll_threaded_vec_with_status(eastings, northings, status):
+        Crs::Etrs89Grid => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_osgb36_to_etrs89_threaded_vec_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::Etrs89Grid;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_osgb36_to_ll_threaded_vec_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::Etrs89;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_epsg3857_to_wgs84_threaded_vec_with_status(x, y, status):
+        Crs::WebMercator => Crs::Wgs84;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_wgs84_to_epsg3857`](fn.convert_wgs84_to_epsg3857.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_wgs84_to_epsg3857_threaded_vec_with_status(longitudes, latitudes, status):
+        Crs::Wgs84 => Crs::WebMercator;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_epsg3857`](fn.convert_osgb36_to_epsg3857.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_osgb36_to_epsg3857_threaded_vec_with_status(eastings, northings, status):
+        Crs::Osgb36 => Crs::WebMercator;
+
+    /// A threaded wrapper for [`lonlat_bng::convert_epsg3857_to_osgb36`](fn.convert_epsg3857_to_osgb36.html), which also writes
+    /// a [`Status`](enum.Status.html) code for each point into `status`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `status` and the coordinate slices differ in length
+    fn convert_epsg3857_to_osgb36_threaded_vec_with_status(x, y, status):
+        Crs::WebMercator => Crs::Osgb36;
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_etrs89`](fn.convert_etrs89.html)
-pub fn convert_to_etrs89_threaded_vec<'a>(longitudes: &'a mut [f64],
-                                          latitudes: &'a mut [f64])
-                                          -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(longitudes, latitudes, convert_etrs89)
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_3d`](fn.convert_osgb36_3d.html)
+pub fn convert_to_osgb36_3d_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                             latitudes: &'a mut [f64],
+                                             heights: &'a mut [f64])
+                                             -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(longitudes, latitudes, heights, convert_osgb36_3d)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_osgb36`](fn.convert_osgb36.html)
-pub fn convert_to_osgb36_threaded_vec<'a>(longitudes: &'a mut [f64],
-                                          latitudes: &'a mut [f64])
-                                          -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(longitudes, latitudes, convert_osgb36)
+/// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_osgb36_3d`](fn.convert_etrs89_to_osgb36_3d.html)
+pub fn convert_etrs89_to_osgb36_3d_threaded_vec<'a>
+    (eastings: &'a mut [f64],
+     northings: &'a mut [f64],
+     heights: &'a mut [f64])
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(eastings, northings, heights, convert_etrs89_to_osgb36_3d)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_osgb36`](fn.convert_etrs89_to_osgb36.html)
-pub fn convert_etrs89_to_osgb36_threaded_vec<'a>(eastings: &'a mut [f64],
-                                                 northings: &'a mut [f64])
-                                                 -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(eastings, northings, convert_etrs89_to_osgb36)
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_etrs89_3d`](fn.convert_osgb36_to_etrs89_3d.html)
+pub fn convert_osgb36_to_etrs89_3d_threaded_vec<'a>
+    (eastings: &'a mut [f64],
+     northings: &'a mut [f64],
+     heights: &'a mut [f64])
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(eastings, northings, heights, convert_osgb36_to_etrs89_3d)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_ll`](fn.convert_etrs8989_to_ll.html)
-pub fn convert_etrs89_to_ll_threaded_vec<'a>(eastings: &'a mut [f64],
-                                             northings: &'a mut [f64])
-                                             -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(eastings, northings, convert_etrs89_to_ll)
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll_3d`](fn.convert_osgb36_to_ll_3d.html)
+pub fn convert_osgb36_to_ll_3d_threaded_vec<'a>
+    (eastings: &'a mut [f64],
+     northings: &'a mut [f64],
+     heights: &'a mut [f64])
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d(eastings, northings, heights, convert_osgb36_to_ll_3d)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_etrs89`](fn.convert_osgb36_to_etrs89.html)
-pub fn convert_osgb36_to_etrs89_threaded_vec<'a>(eastings: &'a mut [f64],
-                                                 northings: &'a mut [f64])
-                                                 -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(eastings, northings, convert_osgb36_to_etrs89)
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_3d_with_datum`](fn.convert_osgb36_3d_with_datum.html)
+///
+/// The [`VerticalDatum`](enum.VerticalDatum.html) flag for each point is written into `datums`.
+/// Points which can't be converted are set to `NAN`, with a flag of `0`
+///
+/// # Panics
+///
+/// Panics if `datums` and the coordinate slices differ in length
+pub fn convert_to_osgb36_3d_threaded_vec_with_datum<'a>
+    (longitudes: &'a mut [f64],
+     latitudes: &'a mut [f64],
+     heights: &'a mut [f64],
+     datums: &'a mut [u8],
+     flags: &DatumFlags)
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d_datum(longitudes,
+                                latitudes,
+                                heights,
+                                datums,
+                                flags,
+                                convert_osgb36_3d_with_datum)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_ll`](fn.convert_osgb36_to_ll.html)
-pub fn convert_osgb36_to_ll_threaded_vec<'a>(eastings: &'a mut [f64],
-                                             northings: &'a mut [f64])
-                                             -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(eastings, northings, convert_osgb36_to_ll)
+/// A threaded wrapper for [`lonlat_bng::convert_etrs89_to_osgb36_3d_with_datum`](fn.convert_etrs89_to_osgb36_3d_with_datum.html)
+///
+/// The [`VerticalDatum`](enum.VerticalDatum.html) flag for each point is written into `datums`.
+/// Points which can't be converted are set to `NAN`, with a flag of `0`
+///
+/// # Panics
+///
+/// Panics if `datums` and the coordinate slices differ in length
+pub fn convert_etrs89_to_osgb36_3d_threaded_vec_with_datum<'a>
+    (eastings: &'a mut [f64],
+     northings: &'a mut [f64],
+     heights: &'a mut [f64],
+     datums: &'a mut [u8],
+     flags: &DatumFlags)
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d_datum(eastings,
+                                northings,
+                                heights,
+                                datums,
+                                flags,
+                                convert_etrs89_to_osgb36_3d_with_datum)
 }
 
-/// A threaded wrapper for [`lonlat_bng::convert_epsg3857_to_wgs84`](fn.convert_epsg3857_to_wgs84.html)
-pub fn convert_epsg3857_to_wgs84_threaded_vec<'a>(x: &'a mut [f64],
-                                                  y: &'a mut [f64])
-                                                  -> (&'a mut [f64], &'a mut [f64]) {
-    convert_vec_direct(x, y, convert_epsg3857_to_wgs84)
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_etrs89_3d_with_datum`](fn.convert_osgb36_to_etrs89_3d_with_datum.html)
+///
+/// The [`VerticalDatum`](enum.VerticalDatum.html) flag to which each input height refers is
+/// written into `datums`. Points which can't be converted are set to `NAN`, with a flag of `0`
+///
+/// # Panics
+///
+/// Panics if `datums` and the coordinate slices differ in length
+pub fn convert_osgb36_to_etrs89_3d_threaded_vec_with_datum<'a>
+    (eastings: &'a mut [f64],
+     northings: &'a mut [f64],
+     heights: &'a mut [f64],
+     datums: &'a mut [u8],
+     flags: &DatumFlags)
+     -> (&'a mut [f64], &'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct_3d_datum(eastings,
+                                northings,
+                                heights,
+                                datums,
+                                flags,
+                                convert_osgb36_to_etrs89_3d_with_datum)
 }
 
 // Generic function which applies conversion functions to vector or slice chunks within threads
@@ -127,7 +534,7 @@ fn convert_vec_direct<'a, F>(ex: &'a mut [f64],
                              ny: &'a mut [f64],
                              func: F)
                              -> (&'a mut [f64], &'a mut [f64])
-    where F: Fn(&f64, &f64) -> Result<(f64, f64), ()> + Send + Sync + Copy
+    where F: Fn(&f64, &f64) -> Result<(f64, f64), ConversionError> + Send + Sync + Copy
 {
     ex.par_iter_mut().zip(ny.par_iter_mut()).for_each(|p| {
         match func(p.0, p.1) {
@@ -145,6 +552,96 @@ fn convert_vec_direct<'a, F>(ex: &'a mut [f64],
     (ex, ny)
 }
 
+// As convert_vec_direct, but also records the outcome of each conversion in a status slice,
+// so that failures can be matched back to their input points.
+// Panics if the slices differ in length, since some points would otherwise have no status
+fn convert_vec_direct_status<'a, F>(ex: &'a mut [f64],
+                                    ny: &'a mut [f64],
+                                    status: &'a mut [u8],
+                                    func: F)
+                                    -> (&'a mut [f64], &'a mut [f64])
+    where F: Fn(&f64, &f64) -> Result<(f64, f64), ConversionError> + Send + Sync + Copy
+{
+    assert!(ex.len() == ny.len() && ex.len() == status.len(),
+            "status and coordinate slices must have the same length");
+    ex.par_iter_mut().zip(ny.par_iter_mut()).zip(status.par_iter_mut()).for_each(|(p, st)| {
+        match func(p.0, p.1) {
+            Ok(res) => {
+                *p.0 = res.0;
+                *p.1 = res.1;
+                *st = Status::Ok as u8;
+            }
+            Err(err) => {
+                *p.0 = NAN;
+                *p.1 = NAN;
+                *st = Status::from(err) as u8;
+            }
+        }
+    });
+    (ex, ny)
+}
+
+// As convert_vec_direct, for conversions which also transform a height
+fn convert_vec_direct_3d<'a, F>(ex: &'a mut [f64],
+                                ny: &'a mut [f64],
+                                h: &'a mut [f64],
+                                func: F)
+                                -> (&'a mut [f64], &'a mut [f64], &'a mut [f64])
+    where F: Fn(&f64, &f64, &f64) -> Result<(f64, f64, f64), ConversionError> + Send + Sync + Copy
+{
+    ex.par_iter_mut().zip(ny.par_iter_mut()).zip(h.par_iter_mut()).for_each(|(p, ht)| {
+        match func(p.0, p.1, ht) {
+            Ok(res) => {
+                *p.0 = res.0;
+                *p.1 = res.1;
+                *ht = res.2;
+            }
+            Err(_) => {
+                *p.0 = NAN;
+                *p.1 = NAN;
+                *ht = NAN;
+            }
+        }
+    });
+    (ex, ny, h)
+}
+
+// As convert_vec_direct_3d, but also writes the OSGM15 vertical datum of each point into a
+// slice of flags, which is 0 (VerticalDatum::OutsideModel) for failed points.
+// Panics if the slices differ in length, since some points would otherwise have no flag
+fn convert_vec_direct_3d_datum<'a, F>(ex: &'a mut [f64],
+                                      ny: &'a mut [f64],
+                                      h: &'a mut [f64],
+                                      datums: &'a mut [u8],
+                                      flags: &DatumFlags,
+                                      func: F)
+                                      -> (&'a mut [f64], &'a mut [f64], &'a mut [f64])
+    where F: Fn(&f64, &f64, &f64, &DatumFlags)
+                -> Result<(f64, f64, f64, VerticalDatum), ConversionError> + Send + Sync + Copy
+{
+    assert!(ex.len() == ny.len() && ex.len() == h.len() && ex.len() == datums.len(),
+            "datum and coordinate slices must have the same length");
+    ex.par_iter_mut()
+        .zip(ny.par_iter_mut())
+        .zip(h.par_iter_mut())
+        .zip(datums.par_iter_mut())
+        .for_each(|((p, ht), datum)| match func(p.0, p.1, ht, flags) {
+            Ok(res) => {
+                *p.0 = res.0;
+                *p.1 = res.1;
+                *ht = res.2;
+                *datum = res.3 as u8;
+            }
+            Err(_) => {
+                *p.0 = NAN;
+                *p.1 = NAN;
+                *ht = NAN;
+                *datum = VerticalDatum::OutsideModel as u8;
+            }
+        });
+    (ex, ny, h)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,6 +665,18 @@ mod tests {
         assert_eq!(expected, (retval[0], retval2[0]));
     }
 
+    #[test]
+    // Test WGS84 to Google/Bing Maps conversion
+    fn test_wgs84_to_epsg3857() {
+        let lon: &mut [f64] = &mut [-5.625000000783013, 0.];
+        let lat: &mut [f64] = &mut [52.48278022732355, 86.];
+        let (x, y) = convert_wgs84_to_epsg3857_threaded(Array::from(lon), Array::from(lat));
+        let retval: &mut [f64] = x.into();
+        let retval2: &mut [f64] = y.into();
+        assert_eq!((-626172.136, 6887893.493), (retval[0], retval2[0]));
+        assert!(retval[1].is_nan());
+    }
+
     #[test]
     // this test verifies that we aren't mangling memory inside our threads
     fn test_threading() {
@@ -430,6 +939,19 @@ mod tests {
         assert_eq!(651409.804, retval[0]);
     }
 
+    #[test]
+    fn test_threaded_itm_conversion() {
+        let lon_vec: &mut [f64] = &mut [-8., 1.716073973];
+        let lat_vec: &mut [f64] = &mut [53.5, 52.65800783];
+        let (eastings, northings) = convert_to_itm_threaded(Array::from(lon_vec),
+                                                            Array::from(lat_vec));
+        let retval: &mut [f64] = eastings.into();
+        let retval2: &mut [f64] = northings.into();
+        assert_eq!((600000., 750000.), (retval[0], retval2[0]));
+        // Outside Ireland
+        assert!(retval[1].is_nan());
+    }
+
     #[test]
     fn test_threaded_etrs89_to_osgb36_conversion_single() {
         let e_vec: &mut [f64] = &mut [651307.003];
@@ -527,6 +1049,228 @@ mod tests {
         drop_float_array(eastings, northings);
     }
 
+    #[test]
+    fn test_threaded_conversion_with_status() {
+        let lon_vec: &mut [f64] = &mut [1.716073973, 1.85, 1.716073973, NAN];
+        let lat_vec: &mut [f64] = &mut [52.658007833, 52.658007833, 48.0, 52.658007833];
+        let status: &mut [u8] = &mut [255; 4];
+        {
+            let (eastings, _) =
+                convert_to_etrs89_threaded_vec_with_status(lon_vec, lat_vec, status);
+            assert_eq!(651307.003, eastings[0]);
+            assert!(eastings[1].is_nan());
+        }
+        assert_eq!(&[Status::Ok as u8,
+                     Status::LongitudeOutOfBounds as u8,
+                     Status::LatitudeOutOfBounds as u8,
+                     Status::NonFinite as u8],
+                   status);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_short_status_slice() {
+        let lon_vec: &mut [f64] = &mut [1.716073973, 1.716073973];
+        let lat_vec: &mut [f64] = &mut [52.658007833, 52.658007833];
+        let status: &mut [u8] = &mut [255; 1];
+        convert_to_etrs89_threaded_vec_with_status(lon_vec, lat_vec, status);
+    }
+
+    #[test]
+    fn test_reverse_datum_batch_failure() {
+        let flags = DatumFlags::from_reader(::std::io::Cursor::new("")).unwrap();
+        let e_vec: &mut [f64] = &mut [700001.000];
+        let n_vec: &mut [f64] = &mut [313177.450];
+        let h_vec: &mut [f64] = &mut [63.822];
+        let datums: &mut [u8] = &mut [255];
+        let (e, _, h) = convert_osgb36_to_etrs89_3d_threaded_vec_with_datum(e_vec,
+                                                                             n_vec,
+                                                                             h_vec,
+                                                                             datums,
+                                                                             &flags);
+        assert!(e[0].is_nan());
+        assert!(h[0].is_nan());
+        assert_eq!(&[VerticalDatum::OutsideModel as u8], datums);
+    }
+
+    #[test]
+    fn test_ffi_datum_conversion_without_flags() {
+        let path = ::std::ffi::CString::new("/nonexistent/OSTN15_OSGM15_DataFile.txt").unwrap();
+        let flags = unsafe { datum_flags_from_file(path.as_ptr()) };
+        assert!(flags.is_null());
+        let e_vec: &mut [f64] = &mut [651409.804];
+        let n_vec: &mut [f64] = &mut [313177.450];
+        let h_vec: &mut [f64] = &mut [63.822];
+        let datum_vec: &mut [u8] = &mut [255];
+        let datum_arr = Array::from(&mut datum_vec[..]);
+        let (e, _, h) = convert_osgb36_to_etrs89_3d_threaded_with_datum(flags,
+                                                                         Array::from(e_vec),
+                                                                         Array::from(n_vec),
+                                                                         Array::from(h_vec),
+                                                                         datum_arr);
+        let e: &mut [f64] = e.into();
+        let h: &mut [f64] = h.into();
+        assert!(e[0].is_nan());
+        assert!(h[0].is_nan());
+        assert_eq!(&[VerticalDatum::OutsideModel as u8], datum_vec);
+        unsafe { drop_datum_flags(flags) };
+    }
+
+    #[test]
+    fn test_ffi_conversion_with_status() {
+        let e_vec: &mut [f64] = &mut [651307.003, 700001.000];
+        let n_vec: &mut [f64] = &mut [313255.686, 313255.686];
+        let status_vec: &mut [u8] = &mut [255; 2];
+        let e_arr = Array::from(e_vec);
+        let n_arr = Array::from(n_vec);
+        let status_arr = Array::from(&mut status_vec[..]);
+        let (lon, _) = convert_etrs89_to_ll_threaded_with_status(e_arr, n_arr, status_arr);
+        let retval: &mut [f64] = lon.into();
+        assert_eq!(1.71607397, retval[0]);
+        assert!(retval[1].is_nan());
+        assert_eq!(&[0, 3], status_vec);
+    }
+
+    #[test]
+    fn test_ffi_mismatched_status_array() {
+        let e_vec: &mut [f64] = &mut [651307.003, 651307.003];
+        let n_vec: &mut [f64] = &mut [313255.686, 313255.686];
+        let status_vec: &mut [u8] = &mut [255; 1];
+        let e_arr = Array::from(&mut e_vec[..]);
+        let n_arr = Array::from(&mut n_vec[..]);
+        let status_arr = Array::from(&mut status_vec[..]);
+        let (lon, lat) = convert_etrs89_to_ll_threaded_with_status(e_arr, n_arr, status_arr);
+        assert_eq!((0, 0), (lon.len, lat.len));
+        assert_eq!(&[651307.003, 651307.003], e_vec);
+        assert_eq!(&[255], status_vec);
+        let (e, _) = convert_epsg_threaded_with_status(4258,
+                                                       27700,
+                                                       Array::from(&mut e_vec[..]),
+                                                       Array::from(&mut n_vec[..]),
+                                                       Array::from(&mut status_vec[..]));
+        assert_eq!(0, e.len);
+    }
+
+    #[test]
+    fn test_ffi_mismatched_datum_array() {
+        let flags = DatumFlags::from_reader(::std::io::Cursor::new("")).unwrap();
+        let flags = Box::into_raw(Box::new(flags));
+        let e_vec: &mut [f64] = &mut [651409.804, 651409.804];
+        let n_vec: &mut [f64] = &mut [313177.450, 313177.450];
+        let h_vec: &mut [f64] = &mut [63.822, 63.822];
+        let datum_vec: &mut [u8] = &mut [255; 1];
+        let e_arr = Array::from(&mut e_vec[..]);
+        let n_arr = Array::from(&mut n_vec[..]);
+        let h_arr = Array::from(&mut h_vec[..]);
+        let datum_arr = Array::from(&mut datum_vec[..]);
+        let (e, n, h) =
+            convert_osgb36_to_etrs89_3d_threaded_with_datum(flags, e_arr, n_arr, h_arr, datum_arr);
+        assert_eq!((0, 0, 0), (e.len, n.len, h.len));
+        assert_eq!(&[63.822, 63.822], h_vec);
+        assert_eq!(&[255], datum_vec);
+        unsafe { drop_datum_flags(flags) };
+    }
+
+    #[test]
+    fn test_ffi_mismatched_geodesic_arrays() {
+        let lons1: &mut [f64] = &mut [-1., -1.];
+        let lats1: &mut [f64] = &mut [52., 52.];
+        let lons2: &mut [f64] = &mut [0., 0.];
+        let lats2: &mut [f64] = &mut [53.];
+        let (distances, _, _) = geodesic_inverse_threaded(Array::from(&mut lons1[..]),
+                                                          Array::from(&mut lats1[..]),
+                                                          Array::from(&mut lons2[..]),
+                                                          Array::from(&mut lats2[..]));
+        assert_eq!(0, distances.len);
+        assert_eq!(&[-1., -1.], lons1);
+        let (lons, _, _) = geodesic_direct_threaded(Array::from(&mut lons1[..]),
+                                                    Array::from(&mut lats1[..]),
+                                                    Array::from(&mut lons2[..]),
+                                                    Array::from(&mut lats2[..]));
+        assert_eq!(0, lons.len);
+        assert_eq!(&[-1., -1.], lons1);
+    }
+
+    #[test]
+    fn test_ffi_epsg_3d_conversion() {
+        let x: &mut [f64] = &mut [1.716073973];
+        let y: &mut [f64] = &mut [52.658007833];
+        let h: &mut [f64] = &mut [108.050];
+        assert!(epsg_pair_supported_3d(4258, 7405));
+        let (e, n, h) =
+            convert_epsg_3d_threaded(4258, 7405, Array::from(x), Array::from(y), Array::from(h));
+        let (e, n, h): (&mut [f64], &mut [f64], &mut [f64]) = (e.into(), n.into(), h.into());
+        assert_eq!((651409.804, 313177.450, 63.822), (e[0], n[0], h[0]));
+    }
+
+    #[test]
+    fn test_ffi_unsupported_epsg_3d_conversion() {
+        let x: &mut [f64] = &mut [516276.000];
+        let y: &mut [f64] = &mut [173141.000];
+        let h: &mut [f64] = &mut [10.];
+        assert!(!epsg_pair_supported_3d(27700, 7405));
+        let (e, _, h) =
+            convert_epsg_3d_threaded(27700, 7405, Array::from(x), Array::from(y), Array::from(h));
+        let (e, h): (&mut [f64], &mut [f64]) = (e.into(), h.into());
+        assert!(e[0].is_nan());
+        assert!(h[0].is_nan());
+    }
+
+    #[test]
+    fn test_ffi_epsg_conversion() {
+        let x: &mut [f64] = &mut [-626172.1357121646];
+        let y: &mut [f64] = &mut [6887893.4928337997];
+        let (lon, lat) = convert_epsg_threaded(3857, 4326, Array::from(x), Array::from(y));
+        let retval: &mut [f64] = lon.into();
+        let retval2: &mut [f64] = lat.into();
+        assert_eq!((-5.625000000783013, 52.48278022732355), (retval[0], retval2[0]));
+    }
+
+    #[test]
+    fn test_ffi_unsupported_epsg_conversion() {
+        let x: &mut [f64] = &mut [516276.000];
+        let y: &mut [f64] = &mut [173141.000];
+        let status_vec: &mut [u8] = &mut [0];
+        assert!(!epsg_pair_supported(27700, 2193));
+        let (e, _) = convert_epsg_threaded_with_status(27700,
+                                                       2193,
+                                                       Array::from(x),
+                                                       Array::from(y),
+                                                       Array::from(&mut status_vec[..]));
+        let retval: &mut [f64] = e.into();
+        assert!(retval[0].is_nan());
+        assert_eq!(&[Status::UnsupportedEpsg as u8], status_vec);
+    }
+
+    #[test]
+    fn test_threaded_osgb36_3d_conversion_single() {
+        let lon_vec: &mut [f64] = &mut [1.716073973];
+        let lat_vec: &mut [f64] = &mut [52.658007833];
+        let h_vec: &mut [f64] = &mut [108.050];
+        let (eastings, northings, heights) =
+            convert_to_osgb36_3d_threaded(Array::from(lon_vec),
+                                          Array::from(lat_vec),
+                                          Array::from(h_vec));
+        let retval: &mut [f64] = eastings.into();
+        let retval2: &mut [f64] = northings.into();
+        let retval3: &mut [f64] = heights.into();
+        assert_eq!((651409.804, 313177.450, 63.822), (retval[0], retval2[0], retval3[0]));
+    }
+
+    #[test]
+    fn test_threaded_osgb36_to_ll_3d_conversion_single() {
+        let e_vec: &mut [f64] = &mut [651409.804];
+        let n_vec: &mut [f64] = &mut [313177.450];
+        let h_vec: &mut [f64] = &mut [63.822];
+        let (lons, lats, heights) = convert_osgb36_to_ll_3d_threaded(Array::from(e_vec),
+                                                                     Array::from(n_vec),
+                                                                     Array::from(h_vec));
+        let retval: &mut [f64] = lons.into();
+        let retval2: &mut [f64] = lats.into();
+        let retval3: &mut [f64] = heights.into();
+        assert_eq!((1.71607397, 52.65800783, 108.050), (retval[0], retval2[0], retval3[0]));
+    }
+
     #[test]
     fn test_bad_threaded_conversion() {
         // above maximum longitude
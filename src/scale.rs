@@ -0,0 +1,259 @@
+//! This module provides the National Grid point scale factor and meridian convergence
+//!
+//! The point scale factor `k` is the ratio of a short grid distance to the corresponding
+//! distance on the ellipsoid. It is 0.9996012717 on the central meridian (2°W), and
+//! reaches 1 about 180 km either side of it. The convergence `γ` is the clockwise angle from
+//! true north to grid north, in decimal degrees.
+//!
+//! Over a line, the scale factor varies, and the line scale factor is the ratio of the grid
+//! distance to the geodesic distance between the points on the ellipsoid. Ground distances
+//! are further reduced to the ellipsoid by the elevation factor, and the product of the two
+//! gives the combined scale factor which converts distances measured on the ground to grid
+//! distances.
+use conversions::{convert_osgb36_to_etrs89, convert_osgb36_to_etrs89_3d, MAX_EASTING,
+                  MAX_LATITUDE, MAX_LONGITUDE, MAX_NORTHING, MIN_LATITUDE, MIN_LONGITUDE};
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use geodesic::geodesic_inverse;
+use transverse_mercator::TransverseMercator;
+use utils::check;
+use super::convert_vec_direct;
+
+/// Calculate the National Grid point scale factor and meridian convergence (decimal degrees)
+/// at an ETRS89 longitude and latitude
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::scale_and_convergence;
+/// let (k, gamma) = scale_and_convergence(&1.716073973, &52.658007833).unwrap();
+/// ```
+pub fn scale_and_convergence(longitude: &f64,
+                             latitude: &f64)
+                             -> Result<(f64, f64), ConversionError> {
+    let lon = check(*longitude, (MIN_LONGITUDE, MAX_LONGITUDE), Axis::Longitude)?;
+    let lat = check(*latitude, (MIN_LATITUDE, MAX_LATITUDE), Axis::Latitude)?;
+    TransverseMercator::NATIONAL_GRID_ETRS89.scale_and_convergence(&lon, &lat)
+}
+
+/// Calculate the National Grid point scale factor and meridian convergence (decimal degrees)
+/// at an OSGB36 Easting and Northing
+///
+/// The values are those of the National Grid projection on the Airy 1830 ellipsoid, so
+/// OSTN15 isn't needed. They differ from those at the corresponding ETRS89 position by less
+/// than 1 ppm and 0.01°, as the two grids are offset by up to about 100 m
+#[allow(non_snake_case)]
+pub fn scale_and_convergence_osgb36(E: &f64, N: &f64) -> Result<(f64, f64), ConversionError> {
+    check(*E, (0.000, MAX_EASTING), Axis::Easting)?;
+    check(*N, (0.000, MAX_NORTHING), Axis::Northing)?;
+    TransverseMercator::NATIONAL_GRID.grid_scale_and_convergence(E, N)
+}
+
+/// A threaded wrapper for [`lonlat_bng::scale_and_convergence`](fn.scale_and_convergence.html)
+///
+/// The longitudes are replaced by scale factors, and the latitudes by convergences
+pub fn scale_and_convergence_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                              latitudes: &'a mut [f64])
+                                              -> (&'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct(longitudes, latitudes, scale_and_convergence)
+}
+
+/// A threaded wrapper for [`lonlat_bng::scale_and_convergence_osgb36`](fn.scale_and_convergence_osgb36.html)
+///
+/// The eastings are replaced by scale factors, and the northings by convergences
+pub fn scale_and_convergence_osgb36_threaded_vec<'a>(eastings: &'a mut [f64],
+                                                     northings: &'a mut [f64])
+                                                     -> (&'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct(eastings, northings, scale_and_convergence_osgb36)
+}
+
+/// Distances and scale factors along a line between two OSGB36 points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineScale {
+    /// The straight-line distance between the points on the National Grid (m)
+    pub grid_distance: f64,
+    /// The geodesic distance between the points' ETRS89 positions on the GRS80 ellipsoid (m)
+    pub ellipsoidal_distance: f64,
+    /// The line scale factor: the grid distance divided by the ellipsoidal distance
+    pub scale_factor: f64,
+    /// The ellipsoidal distance divided by the distance at the mean ellipsoidal height
+    /// of the line's endpoints. This is 1 if no heights were given
+    pub elevation_factor: f64,
+    /// The product of the line scale factor and the elevation factor
+    pub combined_scale_factor: f64,
+}
+
+impl LineScale {
+    /// Convert a length measured on the ground to a grid length
+    pub fn ground_to_grid(&self, length: &f64) -> f64 {
+        length * self.combined_scale_factor
+    }
+
+    /// Convert a grid length to a length on the ground
+    pub fn grid_to_ground(&self, length: &f64) -> f64 {
+        length / self.combined_scale_factor
+    }
+}
+
+/// Calculate the distances and scale factors along a line between two OSGB36 Eastings and
+/// Northings, using OSTN15 data
+///
+/// The ellipsoidal distance is found by solving the inverse geodesic problem between the
+/// ETRS89 positions of the points, so the line scale factor also includes the small
+/// distortions between ETRS89 and OSGB36 modelled by OSTN15.
+///
+/// If `heights` gives the ODN heights of the two points, their ellipsoidal heights are found
+/// using OSGM15, and used to calculate the elevation factor
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::line_scale_osgb36;
+/// let line = line_scale_osgb36(&651409.804, &313177.450, &641409.804, &303177.450,
+///                              Some((63.822, 40.)))
+///     .unwrap();
+/// let grid_length = line.ground_to_grid(&14140.);
+/// ```
+#[allow(non_snake_case)]
+pub fn line_scale_osgb36(E1: &f64,
+                         N1: &f64,
+                         E2: &f64,
+                         N2: &f64,
+                         heights: Option<(f64, f64)>)
+                         -> Result<LineScale, ConversionError> {
+    let ((e1, n1, h1), (e2, n2, h2)) = match heights {
+        Some((H1, H2)) => (convert_osgb36_to_etrs89_3d(E1, N1, &H1)?,
+                           convert_osgb36_to_etrs89_3d(E2, N2, &H2)?),
+        None => {
+            let (e1, n1) = convert_osgb36_to_etrs89(E1, N1)?;
+            let (e2, n2) = convert_osgb36_to_etrs89(E2, N2)?;
+            ((e1, n1, 0.), (e2, n2, 0.))
+        }
+    };
+    let projection = TransverseMercator::NATIONAL_GRID_ETRS89;
+    let (lon1, lat1) = projection.inverse(&e1, &n1)?;
+    let (lon2, lat2) = projection.inverse(&e2, &n2)?;
+    let (ellipsoidal_distance, _, _) =
+        geodesic_inverse(&lon1, &lat1, &lon2, &lat2, &Ellipsoid::GRS80)?;
+
+    let grid_distance = ((E2 - E1).powi(2) + (N2 - N1).powi(2)).sqrt();
+    let scale_factor = if ellipsoidal_distance > 0. {
+        grid_distance / ellipsoidal_distance
+    } else {
+        // A zero-length line has the point scale factor
+        projection.grid_scale_and_convergence(&e1, &n1)?.0
+    };
+    let (em, nm) = ((e1 + e2) / 2., (n1 + n2) / 2.);
+    let elevation_factor = match heights {
+        Some(_) => {
+            let (_, lat) = projection.inverse(&em, &nm)?;
+            let radius = Ellipsoid::GRS80.mean_radius(&lat);
+            radius / (radius + (h1 + h2) / 2.)
+        }
+        None => 1.,
+    };
+    Ok(LineScale {
+        grid_distance: grid_distance,
+        ellipsoidal_distance: ellipsoidal_distance,
+        scale_factor: scale_factor,
+        elevation_factor: elevation_factor,
+        combined_scale_factor: scale_factor * elevation_factor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use conversions::{convert_etrs89_to_osgb36, F0};
+
+    #[test]
+    fn test_central_meridian() {
+        let (k, gamma) = scale_and_convergence(&-2., &54.).unwrap();
+        assert!((k - F0).abs() < 1e-12);
+        assert_eq!(0., gamma);
+        let (k, gamma) = scale_and_convergence_osgb36(&400000., &500000.).unwrap();
+        assert!((k - F0).abs() < 1e-12);
+        assert_eq!(0., gamma);
+    }
+
+    #[test]
+    fn test_scale_and_convergence() {
+        // Caister Water Tower, 3.7° east of the central meridian
+        let (k, gamma) = scale_and_convergence(&1.716073973, &52.658007833).unwrap();
+        assert!(k > 1.0003 && k < 1.0004);
+        assert!(gamma > 2.9 && gamma < 3.);
+        let (grid_k, grid_gamma) = scale_and_convergence_osgb36(&651409.792, &313177.448)
+            .unwrap();
+        assert!((grid_k - k).abs() < 0.000001);
+        assert!((grid_gamma - gamma).abs() < 0.01);
+        // West of the central meridian, grid north lies west of true north
+        let (_, gamma) = scale_and_convergence(&-5., &56.).unwrap();
+        assert!(gamma < 0.);
+    }
+
+    #[test]
+    fn test_threaded_scale_and_convergence() {
+        let eastings: &mut [f64] = &mut [400000., 700000.001];
+        let northings: &mut [f64] = &mut [500000., 500000.];
+        let (k, gamma) = scale_and_convergence_osgb36_threaded_vec(eastings, northings);
+        assert!((k[0] - F0).abs() < 1e-12);
+        assert_eq!(0., gamma[0]);
+        assert!(k[1].is_nan());
+    }
+
+    #[test]
+    fn test_line_scale_factor() {
+        // Integrate the point scale factor along a long line in small steps, on the ETRS89 grid
+        let projection = TransverseMercator::NATIONAL_GRID_ETRS89;
+        let (start, end) = ((651307.003, 313255.686), (451307.003, 113255.686));
+        let steps = 1000;
+        let mut total = 0.;
+        for i in 0..steps {
+            let t = (i as f64 + 0.5) / steps as f64;
+            let e = start.0 + t * (end.0 - start.0);
+            let n = start.1 + t * (end.1 - start.1);
+            total += projection.grid_scale_and_convergence(&e, &n).unwrap().0;
+        }
+        let integrated = total / steps as f64;
+        let (e1, n1) = convert_etrs89_to_osgb36(&start.0, &start.1).unwrap();
+        let (e2, n2) = convert_etrs89_to_osgb36(&end.0, &end.1).unwrap();
+        let line = line_scale_osgb36(&e1, &n1, &e2, &n2, None).unwrap();
+        let etrs89_distance = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt();
+        // Integrating along the chord rather than the geodesic's image, the two agree to 0.1 ppm
+        assert!((line.ellipsoidal_distance * integrated - etrs89_distance).abs() <
+                etrs89_distance * 1e-7);
+        // The ellipsoidal distance is the geodesic distance
+        let (lon1, lat1) = projection.inverse(&start.0, &start.1).unwrap();
+        let (lon2, lat2) = projection.inverse(&end.0, &end.1).unwrap();
+        let (geodesic, _, _) = geodesic_inverse(&lon1, &lat1, &lon2, &lat2, &Ellipsoid::GRS80)
+            .unwrap();
+        assert_eq!(geodesic, line.ellipsoidal_distance);
+        assert!((line.scale_factor - integrated).abs() < 0.00001);
+        assert_eq!(1., line.elevation_factor);
+        assert_eq!(line.scale_factor, line.combined_scale_factor);
+    }
+
+    #[test]
+    fn test_elevation_factor() {
+        let low = line_scale_osgb36(&651409.804, &313177.450, &651509.804, &313177.450,
+                                    Some((0., 0.)))
+            .unwrap();
+        let high = line_scale_osgb36(&651409.804, &313177.450, &651509.804, &313177.450,
+                                     Some((1000., 1000.)))
+            .unwrap();
+        // 1 km higher shortens grid distances by about 157 ppm
+        let ppm = (low.elevation_factor - high.elevation_factor) * 1e6;
+        assert!(ppm > 155. && ppm < 159.);
+        assert!(high.combined_scale_factor < high.scale_factor);
+        let ground = high.grid_to_ground(&100.);
+        assert!((high.ground_to_grid(&ground) - 100.).abs() < 1e-9);
+        assert!((high.grid_distance - 100.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_length_line() {
+        let line = line_scale_osgb36(&400000., &500000., &400000., &500000., None).unwrap();
+        assert_eq!(0., line.grid_distance);
+        assert!((line.scale_factor - F0).abs() < 0.00001);
+    }
+}
@@ -0,0 +1,266 @@
+//! This module provides tile matrix sets: pyramids of tiles laid out on a projected grid, as
+//! used by the EPSG:27700 tile services published by Ordnance Survey
+//!
+//! Each zoom level of a tile matrix set has its own resolution (m per pixel). As with
+//! [`Tile`](struct.Tile.html), tiles are numbered by column (`x`) from the west, and by row
+//! (`y`) from the north, starting at the set's top-left origin.
+use conversions::convert_osgb36;
+use errors::{Axis, ConversionError};
+use tiles::BoundingBox;
+use utils::check;
+
+// The OS Maps API EPSG:27700 tile matrix set
+const OS_BNG_ORIGIN: (f64, f64) = (-238375.0, 1376256.0);
+const OS_BNG_EXTENT: (f64, f64, f64, f64) = (-238375.0, 0.0, 900000.0, 1376256.0);
+const OS_BNG_RESOLUTIONS: [f64; 14] = [896.0, 448.0, 224.0, 112.0, 56.0, 28.0, 14.0, 7.0, 3.5,
+                                       1.75, 0.875, 0.4375, 0.21875, 0.109375];
+
+/// A tile in a [`TileMatrixSet`](struct.TileMatrixSet.html), given by its zoom level,
+/// column and row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileIndex {
+    pub zoom: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileIndex {
+    pub fn new(zoom: u32, x: u32, y: u32) -> TileIndex {
+        TileIndex {
+            zoom: zoom,
+            x: x,
+            y: y,
+        }
+    }
+}
+
+/// A tile matrix set on the British National Grid, or another projected grid
+///
+/// `origin_x` and `origin_y` are the coordinates of the top-left corner of the top-left tile,
+/// `tile_size` is the width and height of a tile in pixels, `resolutions` gives the size of a
+/// pixel (m) at each zoom level, starting from zoom 0, and `extent` is the area the tiles cover
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMatrixSet {
+    pub origin_x: f64,
+    pub origin_y: f64,
+    pub tile_size: u32,
+    pub resolutions: Vec<f64>,
+    pub extent: BoundingBox,
+}
+
+impl TileMatrixSet {
+    pub fn new(origin_x: f64,
+               origin_y: f64,
+               tile_size: u32,
+               resolutions: Vec<f64>,
+               extent: BoundingBox)
+               -> TileMatrixSet {
+        TileMatrixSet {
+            origin_x: origin_x,
+            origin_y: origin_y,
+            tile_size: tile_size,
+            resolutions: resolutions,
+            extent: extent,
+        }
+    }
+
+    /// The EPSG:27700 tile matrix set used by the OS Maps API: 256 px tiles, and zoom levels
+    /// 0 (896 m per pixel) to 13 (0.109375 m per pixel)
+    pub fn os_bng() -> TileMatrixSet {
+        TileMatrixSet::new(OS_BNG_ORIGIN.0,
+                           OS_BNG_ORIGIN.1,
+                           256,
+                           OS_BNG_RESOLUTIONS.to_vec(),
+                           BoundingBox::new(OS_BNG_EXTENT.0,
+                                            OS_BNG_EXTENT.1,
+                                            OS_BNG_EXTENT.2,
+                                            OS_BNG_EXTENT.3))
+    }
+
+    // The width and height of a tile (m) at a zoom level
+    fn tile_span(&self, zoom: u32) -> Result<f64, ConversionError> {
+        self.resolutions
+            .get(zoom as usize)
+            .map(|res| res * self.tile_size as f64)
+            .ok_or(ConversionError::InvalidTile {
+                zoom: zoom,
+                x: 0,
+                y: 0,
+            })
+    }
+
+    // The column and row of the last tile at a zoom level, which contains the
+    // south-east corner of the extent
+    fn last_tile(&self, span: f64) -> (u32, u32) {
+        let x = ((self.extent.max_x - self.origin_x) / span).ceil() - 1.;
+        let y = ((self.origin_y - self.extent.min_y) / span).ceil() - 1.;
+        (x.max(0.) as u32, y.max(0.) as u32)
+    }
+
+    /// The number of columns and rows of tiles at `zoom`
+    pub fn matrix_size(&self, zoom: u32) -> Result<(u32, u32), ConversionError> {
+        let (x, y) = self.last_tile(self.tile_span(zoom)?);
+        Ok((x + 1, y + 1))
+    }
+
+    /// The tile at `zoom` which contains an Easting and Northing
+    ///
+    /// Points on a boundary between tiles belong to the tile to their east or south,
+    /// except on the eastern and southern edges of the extent
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::{TileIndex, TileMatrixSet};
+    /// let tms = TileMatrixSet::os_bng();
+    /// assert_eq!(TileIndex::new(7, 428, 667), tms.tile(&530000., &180000., 7).unwrap());
+    /// ```
+    #[allow(non_snake_case)]
+    pub fn tile(&self, E: &f64, N: &f64, zoom: u32) -> Result<TileIndex, ConversionError> {
+        let e = check(*E, (self.extent.min_x, self.extent.max_x), Axis::Easting)?;
+        let n = check(*N, (self.extent.min_y, self.extent.max_y), Axis::Northing)?;
+        let span = self.tile_span(zoom)?;
+        let (last_x, last_y) = self.last_tile(span);
+        let x = ((e - self.origin_x) / span).floor().max(0.) as u32;
+        let y = ((self.origin_y - n) / span).floor().max(0.) as u32;
+        Ok(TileIndex::new(zoom, x.min(last_x), y.min(last_y)))
+    }
+
+    /// The extent of a tile, in the set's coordinates
+    pub fn bounds(&self, tile: &TileIndex) -> Result<BoundingBox, ConversionError> {
+        let (cols, rows) = self.matrix_size(tile.zoom)?;
+        if tile.x >= cols || tile.y >= rows {
+            return Err(ConversionError::InvalidTile {
+                zoom: tile.zoom,
+                x: tile.x,
+                y: tile.y,
+            });
+        }
+        let span = self.tile_span(tile.zoom)?;
+        let min_x = self.origin_x + tile.x as f64 * span;
+        let max_y = self.origin_y - tile.y as f64 * span;
+        Ok(BoundingBox::new(min_x, max_y - span, min_x + span, max_y))
+    }
+
+    /// List the tiles at `zoom` which intersect an area, from north-west to south-east,
+    /// row by row
+    ///
+    /// Only the part of the area which lies within the set's extent is considered
+    pub fn tiles_intersecting(&self,
+                              area: &BoundingBox,
+                              zoom: u32)
+                              -> Result<Vec<TileIndex>, ConversionError> {
+        let span = self.tile_span(zoom)?;
+        let min_x = area.min_x.max(self.extent.min_x);
+        let min_y = area.min_y.max(self.extent.min_y);
+        let max_x = area.max_x.min(self.extent.max_x);
+        let max_y = area.max_y.min(self.extent.max_y);
+        if min_x > max_x || min_y > max_y {
+            return Ok(vec![]);
+        }
+        let (last_x, last_y) = self.last_tile(span);
+        // Tiles which only touch the area along an edge aren't included
+        let first_x = ((min_x - self.origin_x) / span).floor().max(0.) as u32;
+        let first_y = ((self.origin_y - max_y) / span).floor().max(0.) as u32;
+        let end_x = (((max_x - self.origin_x) / span).ceil() - 1.).max(first_x as f64) as u32;
+        let end_y = (((self.origin_y - min_y) / span).ceil() - 1.).max(first_y as f64) as u32;
+        let mut tiles = vec![];
+        for y in first_y..end_y.min(last_y) + 1 {
+            for x in first_x..end_x.min(last_x) + 1 {
+                tiles.push(TileIndex::new(zoom, x, y));
+            }
+        }
+        Ok(tiles)
+    }
+
+    /// List the tiles at `zoom` which intersect an area given as an ETRS89 longitude and
+    /// latitude bounding box, using OSTN15 data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::{BoundingBox, TileMatrixSet};
+    /// let london = BoundingBox::new(-0.2, 51.45, 0.0, 51.55);
+    /// let tiles = TileMatrixSet::os_bng().tiles_intersecting_lonlat(&london, 9).unwrap();
+    /// ```
+    pub fn tiles_intersecting_lonlat(&self,
+                                     area: &BoundingBox,
+                                     zoom: u32)
+                                     -> Result<Vec<TileIndex>, ConversionError> {
+        let area = area.transform(convert_osgb36)?;
+        self.tiles_intersecting(&area, zoom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_os_bng_matrix_size() {
+        let tms = TileMatrixSet::os_bng();
+        assert_eq!(Ok((5, 6)), tms.matrix_size(0));
+        assert_eq!(Ok((40, 48)), tms.matrix_size(3));
+        assert!(tms.matrix_size(14).is_err());
+    }
+
+    #[test]
+    fn test_os_bng_tile() {
+        let tms = TileMatrixSet::os_bng();
+        let tile = tms.tile(&530000., &180000., 7).unwrap();
+        assert_eq!(TileIndex::new(7, 428, 667), tile);
+        let bounds = tms.bounds(&tile).unwrap();
+        assert!(bounds.min_x <= 530000. && 530000. < bounds.max_x);
+        assert!(bounds.min_y < 180000. && 180000. <= bounds.max_y);
+        assert_eq!(1792., bounds.max_x - bounds.min_x);
+        // The corners of the extent
+        assert_eq!(TileIndex::new(0, 0, 0), tms.tile(&-238375., &1376256., 0).unwrap());
+        assert_eq!(TileIndex::new(0, 4, 5), tms.tile(&900000., &0., 0).unwrap());
+        assert!(tms.tile(&900000.001, &0., 0).is_err());
+    }
+
+    #[test]
+    fn test_bad_tile() {
+        let tms = TileMatrixSet::os_bng();
+        assert_eq!(Err(ConversionError::InvalidTile {
+                       zoom: 0,
+                       x: 5,
+                       y: 0,
+                   }),
+                   tms.bounds(&TileIndex::new(0, 5, 0)));
+    }
+
+    #[test]
+    fn test_tiles_intersecting() {
+        let tms = TileMatrixSet::os_bng();
+        let tile = tms.tile(&530000., &180000., 7).unwrap();
+        let bounds = tms.bounds(&tile).unwrap();
+        // A tile intersects only itself, not its neighbours
+        assert_eq!(vec![tile], tms.tiles_intersecting(&bounds, 7).unwrap());
+        let area = BoundingBox::new(bounds.min_x - 1., bounds.min_y, bounds.max_x, bounds.max_y);
+        assert_eq!(vec![TileIndex::new(7, 427, 667), tile],
+                   tms.tiles_intersecting(&area, 7).unwrap());
+        let outside = BoundingBox::new(1000000., 0., 1100000., 100000.);
+        assert!(tms.tiles_intersecting(&outside, 7).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_custom_tile_matrix_set() {
+        let tms = TileMatrixSet::new(0.,
+                                     1000.,
+                                     100,
+                                     vec![10., 1.],
+                                     BoundingBox::new(0., 0., 1000., 1000.));
+        assert_eq!(Ok((10, 10)), tms.matrix_size(1));
+        assert_eq!(TileIndex::new(1, 0, 9), tms.tile(&50., &50., 1).unwrap());
+    }
+
+    #[test]
+    fn test_tiles_intersecting_lonlat() {
+        let tms = TileMatrixSet::os_bng();
+        let (e, n) = convert_osgb36(&-0.1, &51.5).unwrap();
+        let tiles = tms.tiles_intersecting_lonlat(&BoundingBox::new(-0.2, 51.45, 0.0, 51.55), 9)
+            .unwrap();
+        assert!(tiles.contains(&tms.tile(&e, &n, 9).unwrap()));
+    }
+}
@@ -0,0 +1,282 @@
+//! This module provides slippy map tile helpers for the Web Mercator (EPSG:3857) tile grid
+//! used by Google Maps, Bing Maps and OpenStreetMap
+//!
+//! At zoom level `z` the square Web Mercator map is divided into 2<sup>z</sup> × 2<sup>z</sup>
+//! tiles. Columns are numbered from the west. Rows are numbered from the north in the XYZ
+//! scheme, and from the south in the TMS scheme.
+use std::f64;
+
+use conversions::{convert_epsg3857_to_osgb36, convert_epsg3857_to_wgs84,
+                  convert_osgb36_to_epsg3857, WEB_MERCATOR_EXTENT};
+use errors::{Axis, ConversionError};
+use utils::check;
+
+// Beyond this, tile indices no longer fit in a u32
+pub const MAX_ZOOM: u32 = 31;
+// Projected bounding boxes are found by transforming this many points along each edge
+const EDGE_SAMPLES: usize = 16;
+
+/// The order in which tile rows are numbered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileScheme {
+    /// Rows are numbered from the north, as used by Google Maps and OpenStreetMap
+    Xyz,
+    /// Rows are numbered from the south, as in the OSGeo Tile Map Service specification
+    Tms,
+}
+
+/// An axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> BoundingBox {
+        BoundingBox {
+            min_x: min_x,
+            min_y: min_y,
+            max_x: max_x,
+            max_y: max_y,
+        }
+    }
+
+    /// Transform the box using `f`, returning the bounding box of the result
+    ///
+    /// The edges of the box are sampled, rather than just its corners, as straight lines
+    /// in one system are generally curved in another
+    pub fn transform<F>(&self, f: F) -> Result<BoundingBox, ConversionError>
+        where F: Fn(&f64, &f64) -> Result<(f64, f64), ConversionError>
+    {
+        let mut bbox = BoundingBox::new(f64::INFINITY,
+                                        f64::INFINITY,
+                                        f64::NEG_INFINITY,
+                                        f64::NEG_INFINITY);
+        for i in 0..EDGE_SAMPLES + 1 {
+            let t = i as f64 / EDGE_SAMPLES as f64;
+            let x = self.min_x + t * (self.max_x - self.min_x);
+            let y = self.min_y + t * (self.max_y - self.min_y);
+            for &(px, py) in &[(x, self.min_y), (x, self.max_y), (self.min_x, y), (self.max_x, y)] {
+                let (tx, ty) = f(&px, &py)?;
+                bbox.min_x = bbox.min_x.min(tx);
+                bbox.min_y = bbox.min_y.min(ty);
+                bbox.max_x = bbox.max_x.max(tx);
+                bbox.max_y = bbox.max_y.max(ty);
+            }
+        }
+        Ok(bbox)
+    }
+}
+
+/// A Web Mercator map tile
+///
+/// The row is stored in the XYZ scheme; use [`y`](#method.y) to obtain it in either scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    zoom: u32,
+    x: u32,
+    y: u32,
+}
+
+impl Tile {
+    /// Create a tile from its zoom level, column and row, with the row numbered using `scheme`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::{Tile, TileScheme};
+    /// let xyz = Tile::new(10, 511, 340, TileScheme::Xyz).unwrap();
+    /// let tms = Tile::new(10, 511, 683, TileScheme::Tms).unwrap();
+    /// assert_eq!(xyz, tms);
+    /// ```
+    pub fn new(zoom: u32, x: u32, y: u32, scheme: TileScheme) -> Result<Tile, ConversionError> {
+        let invalid = ConversionError::InvalidTile {
+            zoom: zoom,
+            x: x,
+            y: y,
+        };
+        if zoom > MAX_ZOOM {
+            return Err(invalid);
+        }
+        let count = 1u64 << zoom;
+        if x as u64 >= count || y as u64 >= count {
+            return Err(invalid);
+        }
+        Ok(Tile {
+            zoom: zoom,
+            x: x,
+            y: match scheme {
+                TileScheme::Xyz => y,
+                TileScheme::Tms => (count - 1 - y as u64) as u32,
+            },
+        })
+    }
+
+    /// The tile at `zoom` which contains a Web Mercator point
+    ///
+    /// Points on a boundary between tiles belong to the tile to their east or south,
+    /// except on the eastern and southern edges of the map
+    pub fn containing(x: &f64, y: &f64, zoom: u32) -> Result<Tile, ConversionError> {
+        let x = check(*x, (-WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT), Axis::X)?;
+        let y = check(*y, (-WEB_MERCATOR_EXTENT, WEB_MERCATOR_EXTENT), Axis::Y)?;
+        if zoom > MAX_ZOOM {
+            return Err(ConversionError::InvalidTile {
+                zoom: zoom,
+                x: 0,
+                y: 0,
+            });
+        }
+        let count = (1u64 << zoom) as f64;
+        let index = |offset: f64| {
+            let i = (offset / (2. * WEB_MERCATOR_EXTENT) * count).floor();
+            i.max(0.).min(count - 1.) as u32
+        };
+        Ok(Tile {
+            zoom: zoom,
+            x: index(x + WEB_MERCATOR_EXTENT),
+            y: index(WEB_MERCATOR_EXTENT - y),
+        })
+    }
+
+    pub fn zoom(&self) -> u32 {
+        self.zoom
+    }
+
+    pub fn x(&self) -> u32 {
+        self.x
+    }
+
+    /// The tile's row, numbered using `scheme`
+    pub fn y(&self, scheme: TileScheme) -> u32 {
+        match scheme {
+            TileScheme::Xyz => self.y,
+            TileScheme::Tms => ((1u64 << self.zoom) - 1 - self.y as u64) as u32,
+        }
+    }
+
+    /// The tile's extent, in Web Mercator coordinates
+    pub fn web_mercator_bounds(&self) -> BoundingBox {
+        let size = 2. * WEB_MERCATOR_EXTENT / (1u64 << self.zoom) as f64;
+        let min_x = -WEB_MERCATOR_EXTENT + self.x as f64 * size;
+        let max_y = WEB_MERCATOR_EXTENT - self.y as f64 * size;
+        BoundingBox::new(min_x, max_y - size, min_x + size, max_y)
+    }
+
+    /// The tile's extent, in WGS84 longitude and latitude
+    pub fn wgs84_bounds(&self) -> Result<BoundingBox, ConversionError> {
+        // Meridians and parallels are straight lines in Web Mercator, so the corners suffice
+        let bounds = self.web_mercator_bounds();
+        let (min_lon, min_lat) = convert_epsg3857_to_wgs84(&bounds.min_x, &bounds.min_y)?;
+        let (max_lon, max_lat) = convert_epsg3857_to_wgs84(&bounds.max_x, &bounds.max_y)?;
+        Ok(BoundingBox::new(min_lon, min_lat, max_lon, max_lat))
+    }
+
+    /// The bounding box of the tile's extent in OSGB36 Eastings and Northings,
+    /// using OSTN15 data
+    ///
+    /// The whole tile must lie within OSTN15 coverage
+    pub fn osgb36_bounds(&self) -> Result<BoundingBox, ConversionError> {
+        self.web_mercator_bounds().transform(convert_epsg3857_to_osgb36)
+    }
+}
+
+/// List the tiles at `zoom` which cover an extent given in OSGB36 Eastings and Northings,
+/// from north-west to south-east, row by row
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{tiles_covering_osgb36, BoundingBox, Tile, TileScheme};
+/// // Two kilometres square, around Charing Cross
+/// let extent = BoundingBox::new(529000., 179000., 531000., 181000.);
+/// assert_eq!(vec![Tile::new(10, 511, 340, TileScheme::Xyz).unwrap()],
+///            tiles_covering_osgb36(&extent, 10).unwrap());
+/// ```
+pub fn tiles_covering_osgb36(extent: &BoundingBox,
+                             zoom: u32)
+                             -> Result<Vec<Tile>, ConversionError> {
+    let bounds = extent.transform(convert_osgb36_to_epsg3857)?;
+    let north_west = Tile::containing(&bounds.min_x, &bounds.max_y, zoom)?;
+    let south_east = Tile::containing(&bounds.max_x, &bounds.min_y, zoom)?;
+    let mut tiles = vec![];
+    for y in north_west.y..south_east.y + 1 {
+        for x in north_west.x..south_east.x + 1 {
+            tiles.push(Tile {
+                zoom: zoom,
+                x: x,
+                y: y,
+            });
+        }
+    }
+    Ok(tiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_schemes() {
+        let tile = Tile::new(1, 0, 0, TileScheme::Xyz).unwrap();
+        assert_eq!(1, tile.y(TileScheme::Tms));
+        assert_eq!(tile, Tile::new(1, 0, 1, TileScheme::Tms).unwrap());
+        assert_eq!(Err(ConversionError::InvalidTile {
+                       zoom: 1,
+                       x: 0,
+                       y: 2,
+                   }),
+                   Tile::new(1, 0, 2, TileScheme::Tms));
+        assert!(Tile::new(32, 0, 0, TileScheme::Xyz).is_err());
+    }
+
+    #[test]
+    fn test_web_mercator_bounds() {
+        let world = Tile::new(0, 0, 0, TileScheme::Xyz).unwrap().web_mercator_bounds();
+        assert_eq!(BoundingBox::new(-WEB_MERCATOR_EXTENT,
+                                    -WEB_MERCATOR_EXTENT,
+                                    WEB_MERCATOR_EXTENT,
+                                    WEB_MERCATOR_EXTENT),
+                   world);
+        // The north-west quarter of the map
+        let tile = Tile::new(1, 0, 0, TileScheme::Xyz).unwrap().web_mercator_bounds();
+        assert_eq!(BoundingBox::new(-WEB_MERCATOR_EXTENT, 0., 0., WEB_MERCATOR_EXTENT),
+                   tile);
+    }
+
+    #[test]
+    fn test_wgs84_bounds() {
+        let bounds = Tile::new(0, 0, 0, TileScheme::Xyz).unwrap().wgs84_bounds().unwrap();
+        assert_eq!((-180., 180.), (bounds.min_x, bounds.max_x));
+        assert!((bounds.max_y - 85.0511287798).abs() < 0.0000001);
+        assert!((bounds.min_y + 85.0511287798).abs() < 0.0000001);
+    }
+
+    #[test]
+    fn test_containing() {
+        // The tile containing Greenwich at zoom 10 has its western edge on the meridian
+        let tile = Tile::containing(&0., &6711542.475, 10).unwrap();
+        assert_eq!((512, 340), (tile.x(), tile.y(TileScheme::Xyz)));
+        assert_eq!(0., tile.web_mercator_bounds().min_x);
+        // The south-east corner of the map belongs to the last tile
+        let tile = Tile::containing(&WEB_MERCATOR_EXTENT, &-WEB_MERCATOR_EXTENT, 2).unwrap();
+        assert_eq!((3, 3), (tile.x(), tile.y(TileScheme::Xyz)));
+        assert!(Tile::containing(&0., &20037508.35, 2).is_err());
+    }
+
+    #[test]
+    fn test_tiles_covering_osgb36() {
+        let extent = BoundingBox::new(529000., 179000., 531000., 181000.);
+        assert_eq!(vec![Tile::new(0, 0, 0, TileScheme::Xyz).unwrap()],
+                   tiles_covering_osgb36(&extent, 0).unwrap());
+        let tiles = tiles_covering_osgb36(&extent, 14).unwrap();
+        let (x, y) = convert_osgb36_to_epsg3857(&530000., &180000.).unwrap();
+        assert!(tiles.contains(&Tile::containing(&x, &y, 14).unwrap()));
+        // Tiles are listed row by row, from the north-west
+        assert!(tiles.len() > 1);
+        assert!(tiles.windows(2)
+            .all(|pair| (pair[0].y, pair[0].x) < (pair[1].y, pair[1].x)));
+    }
+}
@@ -0,0 +1,570 @@
+//! This module provides a Transverse Mercator projection, parameterised by ellipsoid and origin
+//!
+//! By default, the projection uses the series given in Annexe C of the OS
+//! [transformation user guide](https://www.ordnancesurvey.co.uk/docs/support/guide-coordinate-systems-great-britain.pdf).
+//! The National Grid is one instance of it.
+//!
+//! The OS series is accurate to around a millimetre within the UK, but degrades quickly more
+//! than a few degrees from the central meridian. The Krüger n-series, to sixth order in `n`
+//! as given by C. F. F. Karney,
+//! [Transverse Mercator with an accuracy of a few nanometers](https://doi.org/10.1007/s00190-011-0445-3),
+//! J. Geodesy 85 (2011), remains accurate to a few nanometres 30° or more from it. It can be
+//! chosen for a single call, using [`forward_with`](struct.TransverseMercator.html#method.forward_with)
+//! and [`inverse_with`](struct.TransverseMercator.html#method.inverse_with), or for the whole
+//! crate, by enabling the `kruger` feature.
+use std::f64;
+
+use conversions::{F0, TRUE_ORIGIN_EASTING, TRUE_ORIGIN_NORTHING};
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use utils::check;
+use super::convert_vec_direct;
+
+// Stop iterating for latitude once the meridional arc is within this distance (m) of the target
+const ARC_EPSILON: f64 = 0.001;
+const MAX_ITERATIONS: usize = 100;
+// Stop iterating for the tangent of the latitude once the change is smaller than this
+const TAU_EPSILON: f64 = 1e-14;
+
+/// The series used to calculate a Transverse Mercator projection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TmSeries {
+    /// The series given by the OS transformation user guide (terms I–VI and VII–XIIA)
+    OrdnanceSurvey,
+    /// The Krüger n-series, to sixth order
+    Kruger,
+}
+
+impl Default for TmSeries {
+    /// The series used by [`forward`](struct.TransverseMercator.html#method.forward) and
+    /// [`inverse`](struct.TransverseMercator.html#method.inverse): `Kruger` if the `kruger`
+    /// feature is enabled, and `OrdnanceSurvey` otherwise
+    #[cfg(feature = "kruger")]
+    fn default() -> TmSeries {
+        TmSeries::Kruger
+    }
+
+    #[cfg(not(feature = "kruger"))]
+    fn default() -> TmSeries {
+        TmSeries::OrdnanceSurvey
+    }
+}
+
+// Evaluate a polynomial in n, whose coefficients are given from the constant term
+fn series(coefficients: &[f64], n: f64) -> f64 {
+    coefficients.iter().rev().fold(0., |acc, c| acc * n + c)
+}
+
+// The coefficients of the Krüger series, as powers of n from n^0 to n^6.
+// ALPHA gives the forward series, and BETA the inverse series
+const ALPHA: [[f64; 7]; 6] =
+    [[0., 1. / 2., -2. / 3., 5. / 16., 41. / 180., -127. / 288., 7891. / 37800.],
+     [0., 0., 13. / 48., -3. / 5., 557. / 1440., 281. / 630., -1983433. / 1935360.],
+     [0., 0., 0., 61. / 240., -103. / 140., 15061. / 26880., 167603. / 181440.],
+     [0., 0., 0., 0., 49561. / 161280., -179. / 168., 6601661. / 7257600.],
+     [0., 0., 0., 0., 0., 34729. / 80640., -3418889. / 1995840.],
+     [0., 0., 0., 0., 0., 0., 212378941. / 319334400.]];
+const BETA: [[f64; 7]; 6] =
+    [[0., 1. / 2., -2. / 3., 37. / 96., -1. / 360., -81. / 512., 96199. / 604800.],
+     [0., 0., 1. / 48., 1. / 15., -437. / 1440., 46. / 105., -1118711. / 3870720.],
+     [0., 0., 0., 17. / 480., -37. / 840., -209. / 4480., 5569. / 90720.],
+     [0., 0., 0., 0., 4397. / 161280., -11. / 504., -830251. / 7257600.],
+     [0., 0., 0., 0., 0., 4583. / 161280., -108847. / 3991680.],
+     [0., 0., 0., 0., 0., 0., 20648693. / 638668800.]];
+
+/// A Transverse Mercator projection
+///
+/// `lat0` and `lon0` are the latitude and longitude of the true origin (decimal degrees),
+/// `k0` is the scale factor on the central meridian, and `false_easting` and `false_northing`
+/// are the grid coordinates of the true origin (m)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransverseMercator {
+    pub ellipsoid: Ellipsoid,
+    pub lat0: f64,
+    pub lon0: f64,
+    pub k0: f64,
+    pub false_easting: f64,
+    pub false_northing: f64,
+}
+
+impl TransverseMercator {
+    /// The National Grid projection on the Airy 1830 ellipsoid, used by OSGB36
+    pub const NATIONAL_GRID: TransverseMercator = TransverseMercator {
+        ellipsoid: Ellipsoid::AIRY_1830,
+        lat0: 49.,
+        lon0: -2.,
+        k0: F0,
+        false_easting: TRUE_ORIGIN_EASTING,
+        false_northing: TRUE_ORIGIN_NORTHING,
+    };
+
+    /// The National Grid projection on the GRS80 ellipsoid, which gives the ETRS89 grid
+    /// coordinates to which OSTN15 shifts are applied
+    pub const NATIONAL_GRID_ETRS89: TransverseMercator = TransverseMercator {
+        ellipsoid: Ellipsoid::GRS80,
+        lat0: 49.,
+        lon0: -2.,
+        k0: F0,
+        false_easting: TRUE_ORIGIN_EASTING,
+        false_northing: TRUE_ORIGIN_NORTHING,
+    };
+
+    /// The Irish Transverse Mercator projection, on the GRS80 ellipsoid
+    pub const ITM: TransverseMercator = TransverseMercator {
+        ellipsoid: Ellipsoid::GRS80,
+        lat0: 53.5,
+        lon0: -8.,
+        k0: 0.999820,
+        false_easting: 600000.,
+        false_northing: 750000.,
+    };
+
+    /// The Irish Grid projection, on the Airy Modified ellipsoid
+    pub const IRISH_GRID: TransverseMercator = TransverseMercator {
+        ellipsoid: Ellipsoid::AIRY_MODIFIED,
+        lat0: 53.5,
+        lon0: -8.,
+        k0: 1.000035,
+        false_easting: 200000.,
+        false_northing: 250000.,
+    };
+
+    pub fn new(ellipsoid: Ellipsoid,
+               lat0: f64,
+               lon0: f64,
+               k0: f64,
+               false_easting: f64,
+               false_northing: f64)
+               -> TransverseMercator {
+        TransverseMercator {
+            ellipsoid: ellipsoid,
+            lat0: lat0,
+            lon0: lon0,
+            k0: k0,
+            false_easting: false_easting,
+            false_northing: false_northing,
+        }
+    }
+
+    // The developed meridional arc from the latitude of true origin to phi (radians)
+    fn meridional_arc(&self, phi: &f64) -> f64 {
+        let b = self.ellipsoid.semi_minor();
+        let c = self.ellipsoid.meridian_arc_coefficients();
+        let phi0 = self.lat0.to_radians();
+        let p_plus = *phi + phi0;
+        let p_minus = *phi - phi0;
+
+        b * self.k0 *
+        (c[0] * p_minus - c[1] * p_minus.sin() * p_plus.cos() +
+         c[2] * (2. * p_minus).sin() * (2. * p_plus).cos() -
+         c[3] * (3. * p_minus).sin() * (3. * p_plus).cos())
+    }
+
+    // The transverse and meridional radii of curvature (scaled by k0), and eta squared
+    fn curvature(&self, phi: &f64) -> (f64, f64, f64) {
+        let a = self.ellipsoid.semi_major();
+        let e2 = self.ellipsoid.e2();
+        let sp2 = phi.sin().powi(2);
+        let nu = a * self.k0 * (1. - e2 * sp2).powf(-0.5);
+        let rho = a * self.k0 * (1. - e2) * (1. - e2 * sp2).powf(-1.5);
+        (nu, rho, nu / rho - 1.)
+    }
+
+    // The rectifying radius, multiplied by k0
+    fn kruger_radius(&self) -> f64 {
+        let n = self.ellipsoid.n();
+        self.k0 * self.ellipsoid.semi_major() / (1. + n) *
+        series(&[1., 0., 1. / 4., 0., 1. / 64., 0., 1. / 256.], n)
+    }
+
+    // The tangent of the conformal latitude corresponding to a latitude (radians)
+    fn conformal_tan(&self, phi: &f64) -> f64 {
+        let e = self.ellipsoid.e2().sqrt();
+        let tau = phi.tan();
+        let sigma = (e * (e * tau / (1. + tau.powi(2)).sqrt()).atanh()).sinh();
+        tau * (1. + sigma.powi(2)).sqrt() - sigma * (1. + tau.powi(2)).sqrt()
+    }
+
+    // The Krüger series at the conformal coordinates (ξ′, η′), using the coefficients alpha
+    // (adding) or beta (subtracting)
+    fn kruger_series(&self,
+                     coefficients: &[[f64; 7]; 6],
+                     sign: f64,
+                     xi: f64,
+                     eta: f64)
+                     -> (f64, f64) {
+        let n = self.ellipsoid.n();
+        coefficients.iter().enumerate().fold((xi, eta), |(x, y), (j, c)| {
+            let a = series(c, n);
+            let k = 2. * (j + 1) as f64;
+            (x + sign * a * (k * xi).sin() * (k * eta).cosh(),
+             y + sign * a * (k * xi).cos() * (k * eta).sinh())
+        })
+    }
+
+    // The Krüger series northing of the latitude of true origin, less the false northing
+    fn kruger_origin(&self) -> f64 {
+        let xi0 = self.conformal_tan(&self.lat0.to_radians()).atan();
+        self.kruger_radius() * self.kruger_series(&ALPHA, 1., xi0, 0.).0
+    }
+
+    /// Project a longitude and latitude (decimal degrees) to an easting and northing (m),
+    /// using the default [`TmSeries`](enum.TmSeries.html)
+    pub fn forward(&self, longitude: &f64, latitude: &f64) -> Result<(f64, f64), ConversionError> {
+        self.forward_with(longitude, latitude, TmSeries::default())
+    }
+
+    /// Project a longitude and latitude (decimal degrees) to an easting and northing (m),
+    /// using the given series
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lonlat_bng::{TmSeries, TransverseMercator};
+    /// let tm = TransverseMercator::NATIONAL_GRID_ETRS89;
+    /// let (e, n) = tm.forward_with(&-13.69, &57.6, TmSeries::Kruger).unwrap();
+    /// ```
+    pub fn forward_with(&self,
+                        longitude: &f64,
+                        latitude: &f64,
+                        series: TmSeries)
+                        -> Result<(f64, f64), ConversionError> {
+        let lambda = check(*longitude, (-180., 180.), Axis::Longitude)?.to_radians();
+        let phi = check(*latitude, (-90., 90.), Axis::Latitude)?.to_radians();
+        match series {
+            TmSeries::OrdnanceSurvey => Ok(self.forward_os(lambda, phi)),
+            TmSeries::Kruger => Ok(self.forward_kruger(lambda, phi)),
+        }
+    }
+
+    fn forward_kruger(&self, lambda: f64, phi: f64) -> (f64, f64) {
+        let l = lambda - self.lon0.to_radians();
+        let tau = self.conformal_tan(&phi);
+        let xi = tau.atan2(l.cos());
+        let eta = (l.sin() / (tau.powi(2) + l.cos().powi(2)).sqrt()).asinh();
+        let (xi, eta) = self.kruger_series(&ALPHA, 1., xi, eta);
+        let radius = self.kruger_radius();
+        (self.false_easting + radius * eta,
+         self.false_northing + radius * xi - self.kruger_origin())
+    }
+
+    #[allow(non_snake_case)]
+    fn forward_os(&self, lambda: f64, phi: f64) -> (f64, f64) {
+        let (nu, rho, eta2) = self.curvature(&phi);
+
+        let m = self.meridional_arc(&phi);
+
+        let cp = phi.cos();
+        let sp = phi.sin();
+        let tp = phi.tan();
+        let tp2 = tp.powi(2);
+        let tp4 = tp.powi(4);
+
+        let I = m + self.false_northing;
+        let II = nu / 2. * sp * cp;
+        let III = nu / 24. * sp * cp.powi(3) * (5. - tp2 + 9. * eta2);
+        let IIIA = nu / 720. * sp * cp.powi(5) * (61. - 58. * tp2 + tp4);
+
+        let IV = nu * cp;
+        let V = nu / 6. * cp.powi(3) * (nu / rho - tp2);
+        let VI = nu / 120. * cp.powi(5) * (5. - 18. * tp2 + tp4 + 14. * eta2 - 58. * tp2 * eta2);
+
+        let l = lambda - self.lon0.to_radians();
+        let north = I + II * l.powi(2) + III * l.powi(4) + IIIA * l.powi(6);
+        let east = self.false_easting + IV * l + V * l.powi(3) + VI * l.powi(5);
+        (east, north)
+    }
+
+    /// Convert an easting and northing (m) to a longitude and latitude (decimal degrees),
+    /// using the default [`TmSeries`](enum.TmSeries.html)
+    ///
+    /// Latitude is obtained by an iterative procedure
+    pub fn inverse(&self, eastings: &f64, northings: &f64) -> Result<(f64, f64), ConversionError> {
+        self.inverse_with(eastings, northings, TmSeries::default())
+    }
+
+    /// Convert an easting and northing (m) to a longitude and latitude (decimal degrees),
+    /// using the given series
+    pub fn inverse_with(&self,
+                        eastings: &f64,
+                        northings: &f64,
+                        series: TmSeries)
+                        -> Result<(f64, f64), ConversionError> {
+        let e = check(*eastings, (f64::MIN, f64::MAX), Axis::Easting)? - self.false_easting;
+        let n = check(*northings, (f64::MIN, f64::MAX), Axis::Northing)? - self.false_northing;
+        match series {
+            TmSeries::OrdnanceSurvey => self.inverse_os(e, n),
+            TmSeries::Kruger => self.inverse_kruger(e, n),
+        }
+    }
+
+    fn inverse_kruger(&self, e: f64, n: f64) -> Result<(f64, f64), ConversionError> {
+        let radius = self.kruger_radius();
+        let xi = (n + self.kruger_origin()) / radius;
+        let eta = e / radius;
+        let (xi, eta) = self.kruger_series(&BETA, -1., xi, eta);
+        let target = xi.sin() / (eta.sinh().powi(2) + xi.cos().powi(2)).sqrt();
+        let l = eta.sinh().atan2(xi.cos());
+
+        // Solve for the tangent of the latitude using Newton's method
+        let e2 = self.ellipsoid.e2();
+        let mut tau = target;
+        let mut iterations = 0;
+        loop {
+            let tau_i = self.conformal_tan(&tau.atan());
+            let delta = (target - tau_i) / (1. + tau_i.powi(2)).sqrt() *
+                        (1. + (1. - e2) * tau.powi(2)) /
+                        ((1. - e2) * (1. + tau.powi(2)).sqrt());
+            tau += delta;
+            if delta.abs() < TAU_EPSILON * tau.abs().max(1.) {
+                break;
+            }
+            iterations += 1;
+            if iterations > MAX_ITERATIONS {
+                return Err(ConversionError::NoConvergence);
+            }
+        }
+        Ok(((self.lon0.to_radians() + l).to_degrees(), tau.atan().to_degrees()))
+    }
+
+    #[allow(non_snake_case)]
+    fn inverse_os(&self, e: f64, dN: f64) -> Result<(f64, f64), ConversionError> {
+        let a = self.ellipsoid.semi_major();
+
+        let mut phi = self.lat0.to_radians() + dN / (a * self.k0);
+        let mut m = self.meridional_arc(&phi);
+        let mut iterations = 0;
+        while (dN - m).abs() >= ARC_EPSILON {
+            iterations += 1;
+            if iterations > MAX_ITERATIONS {
+                return Err(ConversionError::NoConvergence);
+            }
+            phi += (dN - m) / (a * self.k0);
+            m = self.meridional_arc(&phi);
+        }
+        let (nu, rho, eta2) = self.curvature(&phi);
+
+        let tp = phi.tan();
+        let tp2 = tp.powi(2);
+        let tp4 = tp.powi(4);
+
+        let VII = tp / (2. * rho * nu);
+        let VIII = tp / (24. * rho * nu.powi(3)) * (5. + 3. * tp2 + eta2 - 9. * tp2 * eta2);
+        let IX = tp / (720. * rho * nu.powi(5)) * (61. + 90. * tp2 + 45. * tp4);
+
+        let sp = 1.0 / phi.cos();
+        let tp6 = tp4 * tp2;
+
+        let X = sp / nu;
+        let XI = sp / (6. * nu.powi(3)) * (nu / rho + 2. * tp2);
+        let XII = sp / (120. * nu.powi(5)) * (5. + 28. * tp2 + 24. * tp4);
+        let XIIA = sp / (5040. * nu.powi(7)) * (61. + 662. * tp2 + 1320. * tp4 + 720. * tp6);
+
+        let lat = phi - VII * e.powi(2) + VIII * e.powi(4) - IX * e.powi(6);
+        let lon = self.lon0.to_radians() + X * e - XI * e.powi(3) + XII * e.powi(5) -
+                  XIIA * e.powi(7);
+        Ok((lon.to_degrees(), lat.to_degrees()))
+    }
+
+    /// The point scale factor `k`, and the meridian convergence `γ` (decimal degrees) at a
+    /// longitude and latitude
+    ///
+    /// `γ` is the clockwise angle from true north to grid north, so it's positive east of the
+    /// central meridian, and a grid bearing is the true bearing minus `γ`
+    pub fn scale_and_convergence(&self,
+                                 longitude: &f64,
+                                 latitude: &f64)
+                                 -> Result<(f64, f64), ConversionError> {
+        let lambda = check(*longitude, (-180., 180.), Axis::Longitude)?.to_radians();
+        let phi = check(*latitude, (-90., 90.), Axis::Latitude)?.to_radians();
+        let (_, _, eta2) = self.curvature(&phi);
+
+        let l = lambda - self.lon0.to_radians();
+        let lc2 = (l * phi.cos()).powi(2);
+        let tp2 = phi.tan().powi(2);
+
+        let gamma = l * phi.sin() *
+                    (1. + lc2 / 3. * (1. + 3. * eta2 + 2. * eta2.powi(2)) +
+                     lc2.powi(2) / 15. * (2. - tp2));
+        let k = self.k0 *
+                (1. + lc2 / 2. * (1. + eta2) +
+                 lc2.powi(2) / 24. * (5. - 4. * tp2 + 14. * eta2 - 28. * tp2 * eta2) +
+                 lc2.powi(3) / 720. * (61. - 148. * tp2 + 16. * tp2.powi(2)));
+        Ok((k, gamma.to_degrees()))
+    }
+
+    /// The point scale factor `k`, and the meridian convergence `γ` (decimal degrees) at an
+    /// easting and northing
+    pub fn grid_scale_and_convergence(&self,
+                                      eastings: &f64,
+                                      northings: &f64)
+                                      -> Result<(f64, f64), ConversionError> {
+        let (lon, lat) = self.inverse(eastings, northings)?;
+        self.scale_and_convergence(&lon, &lat)
+    }
+
+    /// The arc-to-chord correction `t − T` (decimal degrees) at the start of a line between
+    /// two eastings and northings
+    ///
+    /// `T` is the grid bearing of the projected geodesic where it leaves the first point, and
+    /// `t` is the grid bearing of the straight line (chord) to the second point. The projected
+    /// geodesic is concave towards the central meridian
+    #[allow(non_snake_case)]
+    pub fn arc_to_chord(&self,
+                        E1: &f64,
+                        N1: &f64,
+                        E2: &f64,
+                        N2: &f64)
+                        -> Result<f64, ConversionError> {
+        let (_, lat) = self.inverse(&((E1 + E2) / 2.), &((N1 + N2) / 2.))?;
+        let (nu, rho, _) = self.curvature(&lat.to_radians());
+        let x1 = E1 - self.false_easting;
+        let x2 = E2 - self.false_easting;
+        let correction = -(N2 - N1) * (2. * x1 + x2) / (6. * rho * nu);
+        Ok(correction.to_degrees())
+    }
+
+    /// A threaded wrapper for [`forward`](#method.forward)
+    pub fn forward_threaded_vec<'a>(&self,
+                                    longitudes: &'a mut [f64],
+                                    latitudes: &'a mut [f64])
+                                    -> (&'a mut [f64], &'a mut [f64]) {
+        convert_vec_direct(longitudes,
+                           latitudes,
+                           |lon: &f64, lat: &f64| self.forward(lon, lat))
+    }
+
+    /// A threaded wrapper for [`inverse`](#method.inverse)
+    pub fn inverse_threaded_vec<'a>(&self,
+                                    eastings: &'a mut [f64],
+                                    northings: &'a mut [f64])
+                                    -> (&'a mut [f64], &'a mut [f64]) {
+        convert_vec_direct(eastings,
+                           northings,
+                           |e: &f64, n: &f64| self.inverse(e, n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geodesic::geodesic_inverse;
+
+    #[test]
+    fn test_national_grid_forward() {
+        // Annexe C example: 52°39′27.2531″N, 1°43′4.5177″E on Airy 1830
+        let lat = 52. + 39. / 60. + 27.2531 / 3600.;
+        let lon = 1. + 43. / 60. + 4.5177 / 3600.;
+        let (e, n) = TransverseMercator::NATIONAL_GRID.forward(&lon, &lat).unwrap();
+        assert!((e - 651409.903).abs() < 0.001);
+        assert!((n - 313177.270).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_national_grid_inverse() {
+        let (lon, lat) = TransverseMercator::NATIONAL_GRID.inverse(&651409.903, &313177.270)
+            .unwrap();
+        assert!((lat - (52. + 39. / 60. + 27.2531 / 3600.)).abs() < 0.00000003);
+        assert!((lon - (1. + 43. / 60. + 4.5177 / 3600.)).abs() < 0.00000003);
+    }
+
+    #[test]
+    fn test_custom_projection() {
+        // A projection with its origin at the point itself
+        let tm = TransverseMercator::new(Ellipsoid::GRS80, 53., -8., 1., 500., 1000.);
+        let (e, n) = tm.forward(&-8., &53.).unwrap();
+        assert!((e - 500.).abs() < 0.000001);
+        assert!((n - 1000.).abs() < 0.000001);
+        let (lon, lat) = tm.inverse(&20000., &-30000.).unwrap();
+        let (e, n) = tm.forward(&lon, &lat).unwrap();
+        assert!((e - 20000.).abs() < 0.001);
+        assert!((n - -30000.).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_scale_and_convergence() {
+        let tm = TransverseMercator::NATIONAL_GRID_ETRS89;
+        // On the central meridian
+        let (k, gamma) = tm.scale_and_convergence(&-2., &55.).unwrap();
+        assert!((k - F0).abs() < 1e-12);
+        assert_eq!(0., gamma);
+        // Compare with the grid distance and direction of a short step north along the meridian
+        let (lon, lat) = (1.716073973, 52.658007833);
+        let step = 0.00001;
+        let (e1, n1) = tm.forward(&lon, &(lat - step / 2.)).unwrap();
+        let (e2, n2) = tm.forward(&lon, &(lat + step / 2.)).unwrap();
+        let e2_ = tm.ellipsoid.e2();
+        let rho = tm.ellipsoid.semi_major() * (1. - e2_) /
+                  (1. - e2_ * lat.to_radians().sin().powi(2)).powf(1.5);
+        let (k, gamma) = tm.scale_and_convergence(&lon, &lat).unwrap();
+        let grid = ((e2 - e1).powi(2) + (n2 - n1).powi(2)).sqrt();
+        assert!((k - grid / (rho * step.to_radians())).abs() < 1e-7);
+        assert!((gamma - -(e2 - e1).atan2(n2 - n1).to_degrees()).abs() < 1e-6);
+        assert!(gamma > 2.9 && gamma < 3.);
+        let (e, n) = tm.forward(&lon, &lat).unwrap();
+        let (grid_k, grid_gamma) = tm.grid_scale_and_convergence(&e, &n).unwrap();
+        assert!((grid_k - k).abs() < 1e-11);
+        assert!((grid_gamma - gamma).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_kruger_matches_os_series() {
+        // Land's End, St Mary's, St Kilda, Lowestoft, Dover, Out Stack, Lerwick, John o' Groats
+        let places = [(-5.7148, 50.0663), (-6.3220, 49.8917), (-8.5720, 57.8140),
+                      (1.7630, 52.4810), (1.3280, 51.1290), (-0.8620, 60.8570),
+                      (-1.1450, 60.1550), (-3.0700, 58.6440)];
+        for tm in &[TransverseMercator::NATIONAL_GRID, TransverseMercator::NATIONAL_GRID_ETRS89] {
+            for &(lon, lat) in &places {
+                let (e1, n1) = tm.forward_with(&lon, &lat, TmSeries::OrdnanceSurvey).unwrap();
+                let (e2, n2) = tm.forward_with(&lon, &lat, TmSeries::Kruger).unwrap();
+                assert!((e1 - e2).abs() < 0.001);
+                assert!((n1 - n2).abs() < 0.001);
+                let (lon2, lat2) = tm.inverse_with(&e2, &n2, TmSeries::Kruger).unwrap();
+                assert!((lon2 - lon).abs() < 1e-12);
+                assert!((lat2 - lat).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_kruger_meridian() {
+        // Along the central meridian, the northing is the scaled meridian distance
+        let tm = TransverseMercator::NATIONAL_GRID_ETRS89;
+        assert_eq!((400000., -100000.),
+                   tm.forward_with(&-2., &49., TmSeries::Kruger).unwrap());
+        let (e, n) = tm.forward_with(&-2., &60., TmSeries::Kruger).unwrap();
+        let (distance, _, _) = geodesic_inverse(&-2., &49., &-2., &60., &Ellipsoid::GRS80)
+            .unwrap();
+        assert_eq!(400000., e);
+        assert!((n + 100000. - F0 * distance).abs() < 0.000001);
+    }
+
+    #[test]
+    fn test_kruger_far_from_meridian() {
+        let tm = TransverseMercator::NATIONAL_GRID_ETRS89;
+        // Rockall, 11.7° west of the central meridian, and a point 20° east of it
+        let (e1, n1) = tm.forward_with(&-13.69, &57.6, TmSeries::OrdnanceSurvey).unwrap();
+        let (e2, n2) = tm.forward_with(&-13.69, &57.6, TmSeries::Kruger).unwrap();
+        assert!((e1 - e2).abs().max((n1 - n2).abs()) > 0.01);
+        for &series in &[TmSeries::OrdnanceSurvey, TmSeries::Kruger] {
+            let (e, n) = tm.forward_with(&18., &55., series).unwrap();
+            let (lon, lat) = tm.inverse_with(&e, &n, series).unwrap();
+            let error = (lon - 18.).abs().max((lat - 55.).abs());
+            match series {
+                TmSeries::OrdnanceSurvey => assert!(error > 0.0001),
+                TmSeries::Kruger => assert!(error < 1e-12),
+            }
+        }
+    }
+
+    #[test]
+    fn test_threaded_forward() {
+        let lons: &mut [f64] = &mut [1.716073973, 181.];
+        let lats: &mut [f64] = &mut [52.658007833, 52.658007833];
+        let tm = TransverseMercator::NATIONAL_GRID_ETRS89;
+        let (eastings, northings) = tm.forward_threaded_vec(lons, lats);
+        assert!((eastings[0] - 651307.003).abs() < 0.001);
+        assert!((northings[0] - 313255.686).abs() < 0.001);
+        assert!(eastings[1].is_nan());
+    }
+}
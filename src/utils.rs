@@ -1,8 +1,8 @@
 #![doc(html_root_url = "https://urschrei.github.io/lonlat_bng/")]
 //! This module provides utilities to the conversions module
-use std;
-use std::fmt;
 use ostn15_phf::ostn15_lookup;
+use errors::{Axis, ConversionError};
+use datum::{DatumFlags, VerticalDatum};
 
 // fn helmert(lon_vec: [&f64], lat_vec: [&f64]) -> (Vec<f64>, Vec<f64>) {
 //     let t_array = Vec3::new(TX, TY, TZ);
@@ -19,15 +19,32 @@ use ostn15_phf::ostn15_lookup;
 // }
 
 /// Bounds checking for input values
-pub fn check<T>(to_check: T, bounds: (T, T)) -> Result<T, ()>
-    where T: std::cmp::PartialOrd + fmt::Display + Copy
-{
+pub fn check(to_check: f64, bounds: (f64, f64), axis: Axis) -> Result<f64, ConversionError> {
     match to_check {
+        to_check if !to_check.is_finite() => {
+            Err(ConversionError::NonFinite {
+                axis: axis,
+                value: to_check,
+            })
+        }
         to_check if bounds.0 <= to_check && to_check <= bounds.1 => Ok(to_check),
-        _ => Err(()),
+        _ => {
+            Err(ConversionError::OutOfBounds {
+                axis: axis,
+                value: to_check,
+                min: bounds.0,
+                max: bounds.1,
+            })
+        }
     }
 }
 
+/// Wrap a bearing or azimuth (decimal degrees) into the range [0, 360)
+pub fn normalise_bearing(bearing: f64) -> f64 {
+    let bearing = bearing % 360. + 0.;
+    if bearing < 0. { bearing + 360. } else { bearing }
+}
+
 /// Round an Easting or Northing coordinate to the nearest millimetre
 pub trait ToMm {
     fn round_to_mm(self) -> f64;
@@ -47,10 +64,13 @@ pub fn round_to_eight(x: f64, y: f64) -> (f64, f64) {
 }
 
 /// Try to get OSTN15 shift parameters, and calculate offsets
-pub fn get_ostn_ref(x: &i32, y: &i32) -> Result<(f64, f64, f64), ()> {
+pub fn get_ostn_ref(x: &i32, y: &i32) -> Result<(f64, f64, f64), ConversionError> {
     let key = x + (y * 701) + 1;
     // Some or None, so convert to Result, which we can try!
-    let result = ostn15_lookup(&key).ok_or(())?;
+    let result = ostn15_lookup(&key).ok_or(ConversionError::OutsideOstn15 {
+            easting_index: *x,
+            northing_index: *y,
+        })?;
     Ok((result.0, result.1, result.2))
 }
 
@@ -58,7 +78,7 @@ pub fn get_ostn_ref(x: &i32, y: &i32) -> Result<(f64, f64, f64), ()> {
 // See p20 of the transformation user guide at
 // https://www.ordnancesurvey.co.uk/business-and-government/help-and-support/navigation-technology/os-net/formats-for-developers.html
 /// Calculate OSTN15 shifts for a given coordinate
-pub fn ostn15_shifts(x: &f64, y: &f64) -> Result<(f64, f64, f64), ()> {
+pub fn ostn15_shifts(x: &f64, y: &f64) -> Result<(f64, f64, f64), ConversionError> {
     let e_index = (*x / 1000.) as i32;
     let n_index = (*y / 1000.) as i32;
 
@@ -99,6 +119,16 @@ pub fn ostn15_shifts(x: &f64, y: &f64) -> Result<(f64, f64, f64), ()> {
 
 }
 
+/// Calculate OSTN15 shifts for a given coordinate, together with the OSGM15 vertical datum
+/// which governs its geoid height
+pub fn ostn15_shifts_with_datum(x: &f64,
+                                y: &f64,
+                                flags: &DatumFlags)
+                                -> Result<(f64, f64, f64, VerticalDatum), ConversionError> {
+    let (se, sn, sg) = ostn15_shifts(x, y)?;
+    Ok((se, sn, sg, flags.governing(x, y)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,6 +152,15 @@ mod tests {
         assert_eq!(expected, get_ostn_ref(&eastings, &northings).unwrap());
     }
 
+    #[test]
+    fn test_failed_ostn_hashmap_retrieval_error() {
+        let expected = ConversionError::OutsideOstn15 {
+            easting_index: 999,
+            northing_index: 999,
+        };
+        assert_eq!(expected, get_ostn_ref(&999, &999).unwrap_err());
+    }
+
     #[test]
     fn test_ostn15_shift_incorporation() {
         // these are the input values and corrections on p20-21
@@ -137,7 +176,7 @@ mod tests {
         let max_lon = 1.768960;
         let min_lon = -6.379880;
         // below min_lon
-        check(&-6.379881, (&min_lon, &max_lon)).unwrap();
+        check(-6.379881, (min_lon, max_lon), Axis::Longitude).unwrap();
     }
 
     #[test]
@@ -146,7 +185,7 @@ mod tests {
         let max_lat = 55.811741;
         let min_lat = 49.871159;
         // below min lat
-        check(&49.871158, (&min_lat, &max_lat)).unwrap();
+        check(49.871158, (min_lat, max_lat), Axis::Latitude).unwrap();
     }
 
     #[test]
@@ -155,7 +194,7 @@ mod tests {
         let max_lon = 1.768960;
         let min_lon = -6.379880;
         // above max lon
-        check(&1.768961, (&min_lon, &max_lon)).unwrap();
+        check(1.768961, (min_lon, max_lon), Axis::Longitude).unwrap();
     }
 
     #[test]
@@ -164,6 +203,26 @@ mod tests {
         let max_lat = 55.811741;
         let min_lat = 49.871159;
         // above max lat
-        check(&55.811742, (&min_lat, &max_lat)).unwrap();
+        check(55.811742, (min_lat, max_lat), Axis::Latitude).unwrap();
+    }
+
+    #[test]
+    fn test_check_error_details() {
+        let expected = ConversionError::OutOfBounds {
+            axis: Axis::Latitude,
+            value: 55.811742,
+            min: 49.871159,
+            max: 55.811741,
+        };
+        assert_eq!(expected,
+                   check(55.811742, (49.871159, 55.811741), Axis::Latitude).unwrap_err());
+    }
+
+    #[test]
+    fn test_check_nan() {
+        match check(::std::f64::NAN, (49.871159, 55.811741), Axis::Latitude) {
+            Err(ConversionError::NonFinite { axis: Axis::Latitude, .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
     }
 }
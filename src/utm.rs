@@ -0,0 +1,274 @@
+//! This module provides Universal Transverse Mercator (UTM) conversions for the northern
+//! hemisphere zones which cover Great Britain and Ireland: 29, 30 and 31
+//!
+//! ETRS89 / UTM (EPSG:25829–25831) and WGS84 / UTM (EPSG:32629–32631) use the same
+//! projection on (to within 0.1 mm) the same ellipsoid, and the crate treats WGS84 as ETRS89,
+//! so these functions serve both.
+use std::fmt;
+
+use conversions::{convert_osgb36, convert_osgb36_to_ll};
+use crs::{Crs, Transformer};
+use ellipsoid::Ellipsoid;
+use errors::{Axis, ConversionError};
+use transverse_mercator::{TmSeries, TransverseMercator};
+use utils::{check, round_to_eight, ToMm};
+use super::convert_vec_direct;
+
+pub const MIN_UTM_LONGITUDE: f64 = -12.0000;
+pub const MAX_UTM_LONGITUDE: f64 = 6.0000;
+pub const MIN_UTM_LATITUDE: f64 = 0.0000;
+pub const MAX_UTM_LATITUDE: f64 = 84.0000;
+pub const MAX_UTM_EASTING: f64 = 1000000.000;
+pub const MAX_UTM_NORTHING: f64 = 10000000.000;
+
+/// A northern hemisphere UTM zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UtmZone {
+    /// 12°W to 6°W
+    Zone29,
+    /// 6°W to 0°
+    Zone30,
+    /// 0° to 6°E
+    Zone31,
+}
+
+impl fmt::Display for UtmZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}N", self.number())
+    }
+}
+
+impl UtmZone {
+    /// The zone containing a longitude. Points on a zone boundary are assigned to the zone
+    /// to their east
+    pub fn for_longitude(longitude: &f64) -> Result<UtmZone, ConversionError> {
+        // The eastern edge of zone 31 is included, rather than assigned to zone 32
+        let lon = check(*longitude,
+                        (MIN_UTM_LONGITUDE, MAX_UTM_LONGITUDE),
+                        Axis::Longitude)?;
+        Ok(if lon < -6. {
+            UtmZone::Zone29
+        } else if lon < 0. {
+            UtmZone::Zone30
+        } else {
+            UtmZone::Zone31
+        })
+    }
+
+    /// The zone number
+    pub fn number(&self) -> u8 {
+        match *self {
+            UtmZone::Zone29 => 29,
+            UtmZone::Zone30 => 30,
+            UtmZone::Zone31 => 31,
+        }
+    }
+
+    /// The longitude of the zone's central meridian
+    pub fn central_meridian(&self) -> f64 {
+        self.number() as f64 * 6. - 183.
+    }
+
+    /// The zone's Transverse Mercator projection
+    ///
+    /// The UTM conversions project with [`TmSeries::Kruger`](enum.TmSeries.html), since a
+    /// forced zone can put points several degrees from the central meridian
+    pub fn projection(&self) -> TransverseMercator {
+        self.projection_on(&Ellipsoid::GRS80)
+    }
+
+    /// The zone's Transverse Mercator projection on another ellipsoid, such as
+    /// International 1924 for ED50 / UTM
+    pub fn projection_on(&self, ellipsoid: &Ellipsoid) -> TransverseMercator {
+        TransverseMercator::new(*ellipsoid, 0., self.central_meridian(), 0.9996, 500000., 0.)
+    }
+}
+
+// Use the forced zone, or pick one based on the longitude
+fn zone_or_auto(longitude: &f64, zone: Option<UtmZone>) -> Result<UtmZone, ConversionError> {
+    match zone {
+        Some(zone) => Ok(zone),
+        None => UtmZone::for_longitude(longitude),
+    }
+}
+
+/// Perform ETRS89 / WGS84 Longitude, Latitude to UTM conversion
+///
+/// If `zone` is `None`, the zone containing the point is used. Otherwise, the point is
+/// projected into the given zone, even if it lies outside it
+///
+/// # Examples
+///
+/// ```
+/// use lonlat_bng::{convert_utm, UtmZone};
+/// // Zone 30 is chosen automatically
+/// let (easting, northing) = convert_utm(&-3.0, &54.0, None).unwrap();
+/// assert_eq!((500000.000, 5983521.662), (easting, northing));
+/// // Force zone 31
+/// let (easting, northing) = convert_utm(&-0.5, &51.5, Some(UtmZone::Zone31)).unwrap();
+/// ```
+pub fn convert_utm(longitude: &f64,
+                   latitude: &f64,
+                   zone: Option<UtmZone>)
+                   -> Result<(f64, f64), ConversionError> {
+    let lon = check(*longitude,
+                    (MIN_UTM_LONGITUDE, MAX_UTM_LONGITUDE),
+                    Axis::Longitude)?;
+    let lat = check(*latitude, (MIN_UTM_LATITUDE, MAX_UTM_LATITUDE), Axis::Latitude)?;
+    let zone = zone_or_auto(&lon, zone)?;
+    let (east, north) = zone.projection().forward_with(&lon, &lat, TmSeries::Kruger)?;
+    Ok((east.round_to_mm(), north.round_to_mm()))
+}
+
+/// Convert UTM coordinates in the given zone to ETRS89 / WGS84 Lon, Lat
+#[allow(non_snake_case)]
+pub fn convert_utm_to_ll(E: &f64, N: &f64, zone: UtmZone) -> Result<(f64, f64), ConversionError> {
+    check(*E, (0.000, MAX_UTM_EASTING), Axis::Easting)?;
+    check(*N, (0.000, MAX_UTM_NORTHING), Axis::Northing)?;
+    let (lon, lat) = zone.projection().inverse_with(E, N, TmSeries::Kruger)?;
+    Ok(round_to_eight(lon, lat))
+}
+
+/// Convert UTM coordinates in the given zone to OSGB36 Eastings and Northings,
+/// using OSTN15 data
+#[allow(non_snake_case)]
+pub fn convert_utm_to_osgb36(E: &f64,
+                             N: &f64,
+                             zone: UtmZone)
+                             -> Result<(f64, f64), ConversionError> {
+    let (lon, lat) = convert_utm_to_ll(E, N, zone)?;
+    convert_osgb36(&lon, &lat)
+}
+
+/// Convert OSGB36 Eastings and Northings to UTM coordinates, using OSTN15 data
+///
+/// If `zone` is `None`, the zone containing the point is used
+#[allow(non_snake_case)]
+pub fn convert_osgb36_to_utm(E: &f64,
+                             N: &f64,
+                             zone: Option<UtmZone>)
+                             -> Result<(f64, f64), ConversionError> {
+    let (lon, lat) = convert_osgb36_to_ll(E, N)?;
+    convert_utm(&lon, &lat, zone)
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_utm`](fn.convert_utm.html)
+pub fn convert_to_utm_threaded_vec<'a>(longitudes: &'a mut [f64],
+                                       latitudes: &'a mut [f64],
+                                       zone: Option<UtmZone>)
+                                       -> (&'a mut [f64], &'a mut [f64]) {
+    // Without a forced zone, each point is projected into its own zone, so there's no single
+    // Transformer for the batch
+    convert_vec_direct(longitudes,
+                       latitudes,
+                       move |lon: &f64, lat: &f64| convert_utm(lon, lat, zone))
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_utm_to_ll`](fn.convert_utm_to_ll.html)
+pub fn convert_utm_to_ll_threaded_vec<'a>(eastings: &'a mut [f64],
+                                          northings: &'a mut [f64],
+                                          zone: UtmZone)
+                                          -> (&'a mut [f64], &'a mut [f64]) {
+    Transformer::new(Crs::Etrs89Utm(zone), Crs::Etrs89)
+        .transform_threaded_vec(eastings, northings)
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_utm_to_osgb36`](fn.convert_utm_to_osgb36.html)
+pub fn convert_utm_to_osgb36_threaded_vec<'a>(eastings: &'a mut [f64],
+                                              northings: &'a mut [f64],
+                                              zone: UtmZone)
+                                              -> (&'a mut [f64], &'a mut [f64]) {
+    Transformer::new(Crs::Etrs89Utm(zone), Crs::Osgb36)
+        .transform_threaded_vec(eastings, northings)
+}
+
+/// A threaded wrapper for [`lonlat_bng::convert_osgb36_to_utm`](fn.convert_osgb36_to_utm.html)
+pub fn convert_osgb36_to_utm_threaded_vec<'a>(eastings: &'a mut [f64],
+                                              northings: &'a mut [f64],
+                                              zone: Option<UtmZone>)
+                                              -> (&'a mut [f64], &'a mut [f64]) {
+    convert_vec_direct(eastings,
+                       northings,
+                       move |e: &f64, n: &f64| convert_osgb36_to_utm(e, n, zone))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_selection() {
+        assert_eq!(Ok(UtmZone::Zone29), UtmZone::for_longitude(&-7.5));
+        assert_eq!(Ok(UtmZone::Zone30), UtmZone::for_longitude(&-6.));
+        assert_eq!(Ok(UtmZone::Zone31), UtmZone::for_longitude(&0.));
+        assert_eq!(Ok(UtmZone::Zone31), UtmZone::for_longitude(&6.));
+        assert!(UtmZone::for_longitude(&6.5).is_err());
+        assert_eq!(-3., UtmZone::Zone30.central_meridian());
+        assert_eq!("29N", UtmZone::Zone29.to_string());
+    }
+
+    #[test]
+    fn test_central_meridian() {
+        // On the central meridian, the easting is the false easting
+        let (e, _) = convert_utm(&3., &52., None).unwrap();
+        assert_eq!(500000., e);
+        let (e, n) = convert_utm(&-9., &0., None).unwrap();
+        assert_eq!((500000., 0.), (e, n));
+    }
+
+    #[test]
+    fn test_forced_zone() {
+        // 2°E 1°N forced into ETRS89 / UTM zone 30N (EPSG:25830), 5° east of the central
+        // meridian. The expected value is from PROJ's test suite (test/gie/builtins.gie,
+        // +proj=utm +ellps=GRS80 +zone=30): 1057002.405491298, 110955.141175949
+        assert_eq!((1057002.405, 110955.141),
+                   convert_utm(&2., &1., Some(UtmZone::Zone30)).unwrap());
+        // A point in zone 31, forced into zone 30, lies east of zone 30's central meridian
+        let (e, n) = convert_utm(&1.716073973, &52.658007833, Some(UtmZone::Zone30)).unwrap();
+        assert!(e > 800000.);
+        let (lon, lat) = convert_utm_to_ll(&e, &n, UtmZone::Zone30).unwrap();
+        assert!((lon - 1.716073973).abs() < 0.00000002);
+        assert!((lat - 52.658007833).abs() < 0.00000002);
+    }
+
+    #[test]
+    fn test_utm_round_trip() {
+        let (e, n) = convert_utm(&-0.32824866, &51.44533267, None).unwrap();
+        let (lon, lat) = convert_utm_to_ll(&e, &n, UtmZone::Zone30).unwrap();
+        assert!((lon - -0.32824866).abs() < 0.00000002);
+        assert!((lat - 51.44533267).abs() < 0.00000002);
+    }
+
+    #[test]
+    fn test_utm_to_osgb36() {
+        let (e, n) = convert_utm(&1.716073973, &52.658007833, None).unwrap();
+        assert_eq!(convert_osgb36(&1.716073973, &52.658007833),
+                   convert_utm_to_osgb36(&e, &n, UtmZone::Zone31));
+        let eastings: &mut [f64] = &mut [e];
+        let northings: &mut [f64] = &mut [n];
+        let (bng_e, bng_n) =
+            convert_utm_to_osgb36_threaded_vec(eastings, northings, UtmZone::Zone31);
+        assert_eq!(convert_osgb36(&1.716073973, &52.658007833).unwrap(), (bng_e[0], bng_n[0]));
+    }
+
+    #[test]
+    fn test_ed50_projection() {
+        // ED50 / UTM zone 30N uses the International 1924 ellipsoid
+        let tm = UtmZone::Zone30.projection_on(&Ellipsoid::INTERNATIONAL_1924);
+        let (e, n) = tm.forward(&-3., &54.).unwrap();
+        assert_eq!(500000., e);
+        let (_, grs80_n) = convert_utm(&-3., &54., None).unwrap();
+        assert!((n - grs80_n).abs() > 100.);
+        let (lon, lat) = tm.inverse(&e, &n).unwrap();
+        assert!((lon - -3.).abs() < 1e-7 && (lat - 54.).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_threaded_utm() {
+        let lons: &mut [f64] = &mut [-3., -3., 20.];
+        let lats: &mut [f64] = &mut [54., 54., 54.];
+        let (eastings, _) = convert_to_utm_threaded_vec(lons, lats, Some(UtmZone::Zone30));
+        assert_eq!(500000., eastings[0]);
+        assert!(eastings[2].is_nan());
+    }
+}